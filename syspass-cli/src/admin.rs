@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use clap::{arg, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::warn;
+
+use crate::api::{self, Client};
+use crate::output::Format;
+use crate::prompt::ask_for_password;
+use crate::CommandError;
+
+pub const COMMAND_NAME: &str = "admin";
+
+const BACKUP: &str = "backup";
+const EXPORT: &str = "export";
+
+fn path_arg() -> clap::Arg {
+    arg!(--path <DIR> "Directory on the server to write to, defaults to the sysPass backup directory")
+        .required(false)
+        .value_hint(ValueHint::Other)
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Run server maintenance tasks. Requires sysPass 3")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(BACKUP)
+                .about("Back up the sysPass application and database on the server")
+                .arg(path_arg()),
+        )
+        .subcommand(
+            Command::new(EXPORT)
+                .about("Export the sysPass database to XML on the server")
+                .arg(
+                    arg!(-p --password [PASSWORD] "Encrypt the export, prompts for the password if none is given")
+                        .default_missing_value("")
+                        .value_hint(ValueHint::Other),
+                )
+                .arg(path_arg()),
+        )
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
+    let (name, matches) = matches.subcommand().ok_or(CommandError::NotFound)?;
+    let path = matches.get_one::<String>("path").map(String::as_str);
+
+    let files = match name {
+        BACKUP => api_client.run_backup(path)?,
+        EXPORT => {
+            let password = match matches.get_one::<String>("password").map(String::as_str) {
+                Some("") if quiet => Err(api::Error::Other("Could not ask for the export password".to_owned()))?,
+                Some("") => Some(ask_for_password("Export password: ", true)?),
+                password => password.map(ToOwned::to_owned),
+            };
+
+            api_client.run_export(password.as_deref(), path)?
+        }
+        _ => Err(CommandError::NotFound)?,
+    };
+
+    print_files(name, &files, Format::from(matches))?;
+
+    Ok(0)
+}
+
+fn print_files(name: &str, files: &BTreeMap<String, String>, format: Format) -> Result<(), Box<dyn Error>> {
+    if format.is_structured() {
+        return format.print_one(files);
+    }
+
+    warn!(
+        "{} {} finished",
+        "\u{2714}".bright_green(),
+        if name == BACKUP { "Backup" } else { "Export" }
+    );
+    for (kind, file) in files {
+        warn!("  {kind}: {file}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,363 @@
+use std::error::Error;
+
+use clap::{arg, ArgMatches, Command, ValueHint};
+
+use crate::CommandError;
+
+pub const COMMAND_NAME: &str = "agent";
+
+const START: &str = "start";
+const LOCK: &str = "lock";
+const STATUS: &str = "status";
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Keep the API password unlocked between invocations")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(START).about("Run the agent in the foreground").arg(
+                arg!(-t --timeout <SECONDS> "Lock after this many seconds without use")
+                    .required(false)
+                    .default_value("900")
+                    .value_parser(clap::value_parser!(u64))
+                    .value_hint(ValueHint::Other),
+            ),
+        )
+        .subcommand(Command::new(LOCK).about("Forget all cached passwords"))
+        .subcommand(Command::new(STATUS).about("Show whether the agent is running and unlocked"))
+}
+
+pub fn command(matches: &ArgMatches) -> Result<u8, Box<dyn Error>> {
+    let (name, matches) = matches.subcommand().ok_or(CommandError::NotFound)?;
+
+    match name {
+        START => unix::start(*matches.get_one::<u64>("timeout").expect("Timeout has a default")),
+        LOCK => unix::lock(),
+        STATUS => unix::status(),
+        _ => Err(CommandError::NotFound)?,
+    }
+}
+
+pub fn get_password(key: &str) -> Option<String> {
+    unix::get_password(key)
+}
+
+pub fn store_password(key: &str, password: &str) {
+    unix::store_password(key, password);
+}
+
+pub fn forget_password(key: &str) {
+    unix::forget_password(key);
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use colored::Colorize;
+    use log::{debug, warn};
+
+    const SOCKET_DIR: &str = "syspass-cli";
+    const SOCKET_NAME: &str = "agent.sock";
+    const IO_TIMEOUT: Duration = Duration::from_secs(5);
+    const PURGE_INTERVAL: Duration = Duration::from_secs(1);
+
+    struct Entry {
+        password: String,
+        last_used: Instant,
+    }
+
+    pub struct Agent {
+        timeout: Duration,
+        entries: HashMap<String, Entry>,
+    }
+
+    impl Agent {
+        pub fn new(timeout: Duration) -> Self {
+            Self {
+                timeout,
+                entries: HashMap::new(),
+            }
+        }
+
+        pub fn purge(&mut self) {
+            let timeout = self.timeout;
+            self.entries.retain(|_, entry| entry.last_used.elapsed() < timeout);
+        }
+
+        // One request per line: GET <key>, SET <key> <password>, FORGET <key>, LOCK or STATUS
+        pub fn handle(&mut self, request: &str) -> String {
+            self.purge();
+
+            let mut parts = request.splitn(3, ' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("GET"), Some(key), None) => self.entries.get_mut(key).map_or_else(
+                    || "NONE".to_owned(),
+                    |entry| {
+                        entry.last_used = Instant::now();
+                        format!("OK {}", entry.password)
+                    },
+                ),
+                (Some("SET"), Some(key), Some(password)) => {
+                    self.entries.insert(
+                        key.to_owned(),
+                        Entry {
+                            password: password.to_owned(),
+                            last_used: Instant::now(),
+                        },
+                    );
+                    "OK".to_owned()
+                }
+                (Some("FORGET"), Some(key), None) => {
+                    self.entries.remove(key);
+                    "OK".to_owned()
+                }
+                (Some("LOCK"), None, None) => {
+                    self.entries.clear();
+                    "OK".to_owned()
+                }
+                (Some("STATUS"), None, None) => self
+                    .entries
+                    .values()
+                    .map(|entry| self.timeout.saturating_sub(entry.last_used.elapsed()))
+                    .max()
+                    .map_or_else(
+                        || "LOCKED".to_owned(),
+                        |remaining| format!("UNLOCKED {} {}", self.entries.len(), remaining.as_secs()),
+                    ),
+                _ => "ERROR".to_owned(),
+            }
+        }
+    }
+
+    fn socket_path() -> Result<PathBuf, String> {
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| PathBuf::from(dir).join(SOCKET_DIR).join(SOCKET_NAME))
+            .ok_or_else(|| "XDG_RUNTIME_DIR is not set, the agent is not available".to_owned())
+    }
+
+    fn request(line: &str) -> Result<String, Box<dyn Error>> {
+        let stream = UnixStream::connect(socket_path()?)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        (&stream).write_all(format!("{line}\n").as_bytes())?;
+
+        let mut response = String::new();
+        BufReader::new(&stream).read_line(&mut response)?;
+
+        Ok(response.trim_end_matches(['\r', '\n']).to_owned())
+    }
+
+    pub fn get_password(key: &str) -> Option<String> {
+        match request(&format!("GET {key}")) {
+            Ok(response) => response.strip_prefix("OK ").map(ToOwned::to_owned),
+            Err(error) => {
+                debug!("Agent not available: {error}");
+                None
+            }
+        }
+    }
+
+    pub fn store_password(key: &str, password: &str) {
+        if password.contains(['\r', '\n']) {
+            return;
+        }
+
+        if let Err(error) = request(&format!("SET {key} {password}")) {
+            debug!("Agent not available: {error}");
+        }
+    }
+
+    pub fn forget_password(key: &str) {
+        if let Err(error) = request(&format!("FORGET {key}")) {
+            debug!("Agent not available: {error}");
+        }
+    }
+
+    pub fn start(timeout: u64) -> Result<u8, Box<dyn Error>> {
+        let path = socket_path()?;
+
+        if UnixStream::connect(&path).is_ok() {
+            Err(format!("Agent is already running on {}", path.display()))?;
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+        warn!(
+            "{} Agent listening on {}, locking after {timeout} seconds without use",
+            "\u{2714}".bright_green(),
+            path.display()
+        );
+
+        serve(&listener, Duration::from_secs(timeout));
+
+        Ok(0)
+    }
+
+    pub fn serve(listener: &UnixListener, timeout: Duration) {
+        let agent = Arc::new(Mutex::new(Agent::new(timeout)));
+
+        let purger = Arc::clone(&agent);
+        thread::spawn(move || loop {
+            thread::sleep(PURGE_INTERVAL);
+            purger.lock().expect("Agent state is poisoned").purge();
+        });
+
+        for stream in listener.incoming() {
+            if let Err(error) = stream.and_then(|stream| handle_client(&stream, &agent)) {
+                debug!("Agent connection failed: {error}");
+            }
+        }
+    }
+
+    fn handle_client(stream: &UnixStream, agent: &Mutex<Agent>) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+
+        let response = agent
+            .lock()
+            .expect("Agent state is poisoned")
+            .handle(line.trim_end_matches(['\r', '\n']));
+
+        let mut stream = stream;
+        stream.write_all(format!("{response}\n").as_bytes())
+    }
+
+    pub fn lock() -> Result<u8, Box<dyn Error>> {
+        request("LOCK").map_err(|error| format!("Agent is not running: {error}"))?;
+        warn!("{} Agent locked", "\u{2714}".bright_green());
+
+        Ok(0)
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn status() -> Result<u8, Box<dyn Error>> {
+        let Ok(response) = request("STATUS") else {
+            warn!("{} Agent is not running", "\u{2716}".bright_red());
+            return Ok(1);
+        };
+
+        let mut parts = response.split(' ').skip(1);
+        if let (Some(count), Some(remaining)) = (parts.next(), parts.next()) {
+            warn!(
+                "{} Agent is unlocked with {count} password(s), locking in {remaining} seconds",
+                "\u{2714}".bright_green()
+            );
+        } else {
+            warn!("{} Agent is running and locked", "\u{2714}".bright_green());
+        }
+
+        Ok(0)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::{UnixListener, UnixStream};
+        use std::thread;
+        use std::time::Duration;
+
+        use tempfile::tempdir;
+
+        use crate::agent::unix::{serve, Agent};
+
+        #[test]
+        fn test_agent_handle() {
+            let mut agent = Agent::new(Duration::from_mins(1));
+
+            assert_eq!("LOCKED", agent.handle("STATUS"));
+            assert_eq!("NONE", agent.handle("GET host"));
+            assert_eq!("OK", agent.handle("SET host pass with spaces"));
+            assert_eq!("OK pass with spaces", agent.handle("GET host"));
+            assert!(agent.handle("STATUS").starts_with("UNLOCKED 1 "));
+            assert_eq!("OK", agent.handle("FORGET host"));
+            assert_eq!("NONE", agent.handle("GET host"));
+            assert_eq!("OK", agent.handle("SET host pass with spaces"));
+            assert_eq!("OK", agent.handle("LOCK"));
+            assert_eq!("NONE", agent.handle("GET host"));
+            assert_eq!("ERROR", agent.handle("DELETE host"));
+        }
+
+        #[test]
+        fn test_agent_timeout() {
+            let mut agent = Agent::new(Duration::ZERO);
+
+            assert_eq!("OK", agent.handle("SET host password"));
+            assert_eq!("NONE", agent.handle("GET host"));
+            assert_eq!("LOCKED", agent.handle("STATUS"));
+        }
+
+        #[test]
+        fn test_agent_socket() {
+            let dir = tempdir().expect("Failed to create temp dir");
+            let path = dir.path().join("agent.sock");
+            let listener = UnixListener::bind(&path).expect("Failed to bind socket");
+            thread::spawn(move || serve(&listener, Duration::from_mins(1)));
+
+            let send = |line: &str| {
+                let mut stream = UnixStream::connect(&path).expect("Failed to connect");
+                stream
+                    .write_all(format!("{line}\n").as_bytes())
+                    .expect("Failed to write");
+                let mut response = String::new();
+                BufReader::new(&stream)
+                    .read_line(&mut response)
+                    .expect("Failed to read");
+                response
+            };
+
+            assert_eq!("OK\n", send("SET host secret"));
+            assert_eq!("OK secret\n", send("GET host"));
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod unix {
+    use std::error::Error;
+
+    const NOT_SUPPORTED: &str = "The agent is only supported on Unix";
+
+    pub fn get_password(_key: &str) -> Option<String> {
+        None
+    }
+
+    pub const fn store_password(_key: &str, _password: &str) {}
+
+    pub const fn forget_password(_key: &str) {}
+
+    pub fn start(_timeout: u64) -> Result<u8, Box<dyn Error>> {
+        Err(NOT_SUPPORTED)?
+    }
+
+    pub fn lock() -> Result<u8, Box<dyn Error>> {
+        Err(NOT_SUPPORTED)?
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn status() -> Result<u8, Box<dyn Error>> {
+        Err(NOT_SUPPORTED)?
+    }
+}
@@ -1,13 +1,15 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
 
 use colored::Colorize;
 
-use crate::api::account::{Account, ChangePassword, ViewPassword};
+use crate::api::account::{Account, ChangePassword, EditAccount, ViewPassword};
 use crate::api::category::Category;
 use crate::api::client::Client as SyspassClient;
 use crate::api::syspass::v2;
 use crate::api::syspass::v3;
+use crate::api::tag::Tag;
 use crate::api::Api::{SyspassV2, SyspassV3};
 use crate::config::Config;
 
@@ -16,6 +18,10 @@ pub mod category;
 pub mod client;
 pub mod entity;
 mod syspass;
+pub mod tag;
+
+// sysPass only returns the first page of a search unless a count is given
+pub const ACCOUNT_LIMIT: &str = "100000";
 
 pub trait Client {
     fn search_account(&self, search: Vec<(&str, String)>, usage: bool) -> Result<Vec<Account>, Error>;
@@ -26,17 +32,95 @@ pub trait Client {
     fn save_category(&self, category: &Category) -> Result<Category, Error>;
     fn save_account(&self, account: &Account) -> Result<Account, Error>;
     fn change_password(&self, password: &ChangePassword) -> Result<Account, Error>;
+    fn can_change_password(&self) -> bool;
+    fn edit_account(&self, account: &EditAccount) -> Result<Account, Error>;
     fn delete_client(&self, id: u32) -> Result<bool, Error>;
     fn delete_category(&self, id: u32) -> Result<bool, Error>;
     fn delete_account(&self, id: u32) -> Result<bool, Error>;
     fn view_account(&self, id: u32) -> Result<Account, Error>;
     fn get_category(&self, id: u32) -> Result<Category, Error>;
     fn get_client(&self, id: u32) -> Result<SyspassClient, Error>;
+    fn get_tags(&self) -> Result<Vec<Tag>, Error>;
+    fn get_tag(&self, id: u32) -> Result<Tag, Error>;
+    fn save_tag(&self, tag: &Tag) -> Result<Tag, Error>;
+    fn delete_tag(&self, id: u32) -> Result<bool, Error>;
+    fn run_backup(&self, path: Option<&str>) -> Result<BTreeMap<String, String>, Error>;
+    fn run_export(&self, password: Option<&str>, path: Option<&str>) -> Result<BTreeMap<String, String>, Error>;
+    fn get_expiring_accounts(&self) -> Result<Vec<Account>, Error>;
     fn get_config(&self) -> &Config;
 }
 
+pub const NOT_SUPPORTED: &str = "Syspass does not support this";
+
+const AUTHENTICATION_MESSAGES: &[&str] = &["Wrong authentication token", "Wrong token password"];
+const PERMISSION_DENIED_MESSAGES: &[&str] = &["Unauthorized access", "You don't have permission to do this operation"];
+const NOT_FOUND_MESSAGES: &[&str] = &[
+    "The account doesn't exist",
+    "Account not found",
+    "The category doesn't exist",
+    "Category not found",
+    "The client doesn't exist",
+    "Client not found",
+    "Customer not found",
+    "The tag doesn't exist",
+    "Tag not found",
+];
+
 #[derive(Debug)]
-pub struct Error(String);
+pub enum Error {
+    Network(String),
+    Tls(String),
+    HttpStatus(u16),
+    Authentication(String),
+    PermissionDenied(String),
+    NotFound(String),
+    Ambiguous(String),
+    MalformedResponse(String),
+    Unsupported,
+    Aborted,
+    Other(String),
+}
+
+impl Error {
+    pub const fn exit_code(&self) -> u8 {
+        match self {
+            Self::Other(_) => 1,
+            Self::Network(_) => 3,
+            Self::Tls(_) => 4,
+            Self::HttpStatus(_) => 5,
+            Self::Authentication(_) => 6,
+            Self::PermissionDenied(_) => 7,
+            Self::NotFound(_) => 8,
+            Self::Ambiguous(_) => 9,
+            Self::MalformedResponse(_) => 10,
+            Self::Unsupported => 11,
+            Self::Aborted => 130,
+        }
+    }
+
+    // sysPass reports every failure as the same JSON-RPC error code, so only its exact message tells them apart
+    pub fn from_server_message(message: String) -> Self {
+        let message_is = |messages: &[&str]| messages.iter().any(|known| known.eq_ignore_ascii_case(message.trim()));
+
+        if message_is(AUTHENTICATION_MESSAGES) {
+            Self::Authentication(message)
+        } else if message_is(PERMISSION_DENIED_MESSAGES) {
+            Self::PermissionDenied(message)
+        } else if message_is(NOT_FOUND_MESSAGES) {
+            Self::NotFound(message)
+        } else {
+            Self::Other(message)
+        }
+    }
+
+    pub fn from_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            401 => Self::Authentication(format!("Server responded with code {status}")),
+            403 => Self::PermissionDenied(format!("Server responded with code {status}")),
+            code => Self::HttpStatus(code),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct AppError(pub String);
@@ -45,7 +129,44 @@ impl std::error::Error for Error {}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            Self::Network(message) => write!(f, "Could not connect to server: {message}"),
+            Self::Tls(message) => write!(f, "TLS error: {message}"),
+            Self::HttpStatus(code) => write!(f, "Server responded with code {code}"),
+            Self::Authentication(message)
+            | Self::PermissionDenied(message)
+            | Self::NotFound(message)
+            | Self::Ambiguous(message)
+            | Self::Other(message) => write!(f, "{message}"),
+            Self::MalformedResponse(message) => write!(f, "Invalid response: {message}"),
+            Self::Unsupported => write!(f, "{NOT_SUPPORTED}"),
+            Self::Aborted => write!(f, "Aborted"),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        let mut message = error.to_string();
+        let mut source = std::error::Error::source(&error);
+        while let Some(cause) = source {
+            message = format!("{message}: {cause}");
+            source = cause.source();
+        }
+
+        let lower = message.to_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"]
+            .iter()
+            .any(|word| lower.contains(word))
+        {
+            Self::Tls(message)
+        } else if let Some(status) = error.status() {
+            Self::from_status(status)
+        } else if error.is_decode() {
+            Self::MalformedResponse(message)
+        } else {
+            Self::Network(message)
+        }
     }
 }
 
@@ -57,12 +178,6 @@ impl fmt::Display for AppError {
     }
 }
 
-impl From<Error> for AppError {
-    fn from(value: Error) -> Self {
-        Self(value.0)
-    }
-}
-
 pub enum Api {
     SyspassV3,
     SyspassV2,
@@ -95,7 +210,7 @@ mod tests {
 
     use test_case::test_case;
 
-    use crate::api::{Api, AppError};
+    use crate::api::{Api, AppError, Error};
 
     #[test_case("SyspassV3", true)]
     #[test_case("SyspassV2", true)]
@@ -109,6 +224,28 @@ mod tests {
         );
     }
 
+    #[test_case("Wrong authentication token", 6)]
+    #[test_case("Unauthorized access", 7)]
+    #[test_case("Account not found", 8)]
+    #[test_case("The account doesn't exist", 8)]
+    #[test_case("Wrong token password", 6)]
+    #[test_case("You don't have permission to do this operation", 7)]
+    #[test_case("Customer not found", 8)]
+    #[test_case("Failed to add account", 1)]
+    #[test_case("Password is too short", 1)]
+    #[test_case("Duplicated account name", 1)]
+    fn test_from_server_message(message: &str, exit_code: u8) {
+        assert_eq!(exit_code, Error::from_server_message(message.to_owned()).exit_code());
+    }
+
+    #[test_case(401, 6)]
+    #[test_case(403, 7)]
+    #[test_case(500, 5)]
+    fn test_from_status(status: u16, exit_code: u8) {
+        let status = reqwest::StatusCode::from_u16(status).expect("Valid status code");
+        assert_eq!(exit_code, Error::from_status(status).exit_code());
+    }
+
     #[test]
     fn test_display() {
         let error = AppError("test this error".to_owned());
@@ -2,12 +2,12 @@ use std::cmp;
 use std::fmt::{Display, Formatter, Result};
 
 use colored::Colorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::api::entity::Entity;
 use crate::TERMINAL_SIZE;
 
-#[derive(Deserialize, Default, Clone)]
+#[derive(Deserialize, Serialize, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Account {
     id: Option<u32>,
@@ -17,8 +17,16 @@ pub struct Account {
     notes: Option<String>,
     category_id: u32,
     client_id: u32,
+    #[serde(skip_serializing)]
     pass: Option<String>,
     client_name: Option<String>,
+    // Unix timestamps of the last password change and of its expiry, 0 when it doesn't expire. sysPass 3 only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pass_date: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pass_date_change: Option<i64>,
+    #[serde(default)]
+    tags_id: Vec<u32>,
 }
 
 impl Account {
@@ -44,13 +52,16 @@ impl Account {
             client_id,
             pass,
             client_name,
+            pass_date: None,
+            pass_date_change: None,
+            tags_id: Vec::new(),
         }
     }
 
-    pub fn name(&self) -> &str {
+    pub const fn name(&self) -> &str {
         self.name.as_str()
     }
-    pub fn login(&self) -> &str {
+    pub const fn login(&self) -> &str {
         self.login.as_str()
     }
     pub fn url(&self) -> Option<&str> {
@@ -71,6 +82,12 @@ impl Account {
     pub fn client_name(&self) -> Option<&str> {
         self.client_name.as_deref()
     }
+    pub const fn pass_date(&self) -> Option<i64> {
+        self.pass_date
+    }
+    pub fn expire_date(&self) -> Option<i64> {
+        self.pass_date_change.filter(|date| *date > 0)
+    }
 }
 
 impl Display for Account {
@@ -106,8 +123,10 @@ fn truncate(s: &str, max_chars: usize) -> String {
     }
 }
 
+#[derive(Serialize)]
 pub struct ViewPassword {
     pub password: String,
+    #[serde(flatten)]
     pub account: Account,
 }
 
@@ -117,6 +136,20 @@ pub struct ChangePassword {
     pub expire_date: i64,
 }
 
+// sysPass rebuilds the whole account on edit and blanks any field that isn't sent, so every field is always sent
+pub struct EditAccount {
+    pub id: u32,
+    pub name: String,
+    pub category_id: u32,
+    pub client_id: u32,
+    pub login: String,
+    pub url: String,
+    pub notes: String,
+    // Empty leaves the tags as they are. account/view doesn't return them, so an edit can't send the current ones back
+    pub tags_id: Vec<u32>,
+    pub expire_date: i64,
+}
+
 impl Entity for Account {
     fn id(&self) -> Option<&u32> {
         self.id.as_ref()
@@ -129,7 +162,7 @@ impl Entity for Account {
 
 #[cfg(test)]
 mod tests {
-    use crate::api::account::{truncate, Account};
+    use crate::api::account::{truncate, Account, ViewPassword};
 
     #[test]
     fn test_truncate() {
@@ -156,6 +189,7 @@ mod tests {
                     client_id: 0,
                     pass: None,
                     client_name: Some("client_name".to_string()),
+                    ..Default::default()
                 }
                 .to_string()
             )
@@ -174,9 +208,53 @@ mod tests {
                     client_id: 0,
                     pass: None,
                     client_name: None,
+                    ..Default::default()
                 }
                 .to_string()
             )
         );
     }
+
+    #[test]
+    fn test_serialize_view_password() {
+        let view = ViewPassword {
+            password: "secret".to_owned(),
+            account: Account::new(
+                Some(1),
+                "name".to_owned(),
+                "login".to_owned(),
+                Some("example.org".to_owned()),
+                None,
+                2,
+                3,
+                Some("not serialized".to_owned()),
+                Some("client".to_owned()),
+            ),
+        };
+
+        assert_eq!(
+            r#"{"password":"secret","id":1,"name":"name","login":"login","url":"example.org","notes":null,"categoryId":2,"clientId":3,"clientName":"client","tagsId":[]}"#,
+            serde_json::to_string(&view).expect("Serialization failed")
+        );
+    }
+
+    #[test]
+    fn test_expire_date() {
+        let account: Account = serde_json::from_str(
+            r#"{"id":1,"name":"name","login":"login","categoryId":1,"clientId":1,"passDate":1687901528,"passDateChange":1695677528}"#,
+        )
+        .expect("Deserialization failed");
+
+        assert_eq!(Some(1_687_901_528), account.pass_date());
+        assert_eq!(Some(1_695_677_528), account.expire_date());
+        assert!(serde_json::to_string(&account)
+            .expect("Serialization failed")
+            .contains(r#""passDateChange":1695677528"#));
+
+        let account: Account =
+            serde_json::from_str(r#"{"name":"name","login":"login","categoryId":1,"clientId":1,"passDateChange":0}"#)
+                .expect("Deserialization failed");
+        assert_eq!(None, account.expire_date());
+        assert_eq!(None, account.pass_date());
+    }
 }
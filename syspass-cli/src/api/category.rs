@@ -4,7 +4,7 @@ use colored::Colorize;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::FuzzySelect;
 use log::error;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::api;
 use crate::api::entity::Entity;
@@ -12,7 +12,7 @@ use crate::prompt::ask_prompt;
 
 const ID_EMPTY: &str = "Id should not be empty";
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Category {
     id: Option<u32>,
@@ -25,7 +25,7 @@ impl Category {
         Self { id, name, description }
     }
 
-    pub fn name(&self) -> &str {
+    pub const fn name(&self) -> &str {
         self.name.as_str()
     }
 
@@ -59,25 +59,20 @@ impl Entity for Category {
 }
 
 pub fn ask_for(api_client: &dyn api::Client) -> Result<u32, api::Error> {
-    let categories = match api_client.get_categories() {
-        Ok(categories) => categories,
-        Err(error) => {
-            return Err(api::Error(format!("{error}: Could not list categories")));
-        }
-    };
+    let categories = api_client.get_categories()?;
 
     FuzzySelect::with_theme(&ColorfulTheme::default())
         .with_prompt("Select the right category (ESC for new):")
         .max_length(10)
         .items(&categories)
         .interact_opt()
-        .expect("Failed to select category")
+        .map_err(|_| api::Error::Aborted)?
         .map_or_else(
             || loop {
                 let new_category = Category {
                     id: None,
-                    name: ask_prompt("Category name", true, ""),
-                    description: Some(ask_prompt("Category description", false, "")),
+                    name: ask_prompt("Category name", true, "")?,
+                    description: Some(ask_prompt("Category description", false, "")?),
                 };
 
                 match api_client.save_category(&new_category) {
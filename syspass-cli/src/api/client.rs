@@ -5,7 +5,7 @@ use colored::Colorize;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{Confirm, FuzzySelect};
 use log::error;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::api;
 use crate::api::entity::Entity;
@@ -13,7 +13,7 @@ use crate::prompt::ask_prompt;
 
 const ID_EMPTY: &str = "Id should not be empty";
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Client {
     id: Option<u32>,
@@ -32,7 +32,7 @@ impl Client {
         }
     }
 
-    pub fn name(&self) -> &str {
+    pub const fn name(&self) -> &str {
         self.name.as_str()
     }
     pub fn description(&self) -> Option<&str> {
@@ -76,25 +76,20 @@ impl Entity for Client {
 }
 
 pub fn ask_for(api_client: &dyn api::Client, matches: &ArgMatches) -> std::result::Result<u32, api::Error> {
-    let clients = match api_client.get_clients() {
-        Ok(clients) => clients,
-        Err(error) => {
-            return Err(api::Error(format!("{error}: Could not list clients")));
-        }
-    };
+    let clients = api_client.get_clients()?;
 
     FuzzySelect::with_theme(&ColorfulTheme::default())
         .with_prompt("Select the right client (ESC for new):")
         .max_length(10)
         .items(&clients)
         .interact_opt()
-        .expect("Failed to select client")
+        .map_err(|_| api::Error::Aborted)?
         .map_or_else(
             || loop {
                 let new_client = Client {
                     id: None,
-                    name: ask_prompt("Name:", true, ""),
-                    description: Some(ask_prompt("Description:", false, "")),
+                    name: ask_prompt("Name:", true, "")?,
+                    description: Some(ask_prompt("Description:", false, "")?),
                     is_global: matches.get_one::<usize>("global").map_or_else(
                         || usize::from(Confirm::new().with_prompt("Global:").interact().unwrap_or(false)),
                         std::borrow::ToOwned::to_owned,
@@ -1,8 +1,7 @@
-use std::cell::Cell;
+use std::cell::{Cell, OnceCell};
 use std::collections::HashMap;
 
-use log::debug;
-use once_cell::sync::Lazy;
+use log::{debug, log_enabled, Level};
 use reqwest::blocking::{ClientBuilder, Response};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -12,19 +11,22 @@ use crate::api;
 use crate::api::account::Account;
 use crate::api::entity::Entity;
 use crate::config::Config;
-use crate::prompt::ask_for_password;
+use crate::credential;
+use crate::redact::redact;
 
+mod replay;
 pub mod v2;
 pub mod v3;
 
-static PASSWORD: Lazy<String> = Lazy::new(|| ask_for_password("API password: ", false));
-
 type RequestArguments<'key> = Option<Vec<(&'key str, String)>>;
 
+// sysPass reads these as arrays, so their comma separated ids are sent as a list of integers
+const ID_LIST_PARAMS: [&str; 1] = ["tagsId"];
+
 fn sort_accounts(list: &mut [Account], usage_data: &HashMap<u32, u32>) {
     list.sort_by(|a, b| {
-        let left = usage_data.get(a.id().expect("Id is set")).unwrap_or(&0);
-        let right = usage_data.get(b.id().expect("Id is set")).unwrap_or(&0);
+        let left = a.id().and_then(|id| usage_data.get(id)).unwrap_or(&0);
+        let right = b.id().and_then(|id| usage_data.get(id)).unwrap_or(&0);
 
         if *left == 0 && *right == 0 {
             a.id().cmp(&b.id())
@@ -38,8 +40,8 @@ fn sort_accounts(list: &mut [Account], usage_data: &HashMap<u32, u32>) {
 struct JsonReq {
     jsonrpc: String,
     method: String,
-    params: HashMap<String, String>,
-    id: u8,
+    params: HashMap<String, Value>,
+    id: u32,
 }
 
 fn get_builder(config: &Config) -> ClientBuilder {
@@ -47,66 +49,136 @@ fn get_builder(config: &Config) -> ClientBuilder {
 }
 
 fn get_response(client: &reqwest::blocking::Client, request_url: &str, req: &JsonReq) -> Result<Response, api::Error> {
-    match client.post(request_url).json(&req).send() {
-        Ok(r) => {
-            if r.status().is_success() {
-                Ok(r)
-            } else {
-                Err(api::Error(format!("Server responded with code {}", r.status())))
-            }
-        }
-        Err(e) => Err(api::Error(e.to_string())),
+    let response = client.post(request_url).json(&req).send()?;
+
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(api::Error::from_status(response.status()))
+    }
+}
+
+// Every server response goes through here so a missing or mistyped field is reported with its path
+fn parse<T: DeserializeOwned>(value: Value, entity: &str) -> Result<T, api::Error> {
+    serde_path_to_error::deserialize(value).map_err(|error| api::Error::MalformedResponse(format!("{entity}: {error}")))
+}
+
+fn to_param(key: &str, value: String) -> Result<Value, api::Error> {
+    if !ID_LIST_PARAMS.contains(&key) {
+        return Ok(Value::from(value));
     }
+
+    value
+        .split(',')
+        .map(|id| {
+            id.trim()
+                .parse::<u32>()
+                .map_err(|_| api::Error::Other(format!("{key}: {id:?} is not an id")))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::from)
+}
+
+fn get_account_id(account: &Account) -> Result<&u32, api::Error> {
+    account
+        .id()
+        .ok_or_else(|| api::Error::Other(format!("Account {} has no id", account.name())))
+}
+
+fn get_account_pass(account: &Account) -> Result<&str, api::Error> {
+    account
+        .pass()
+        .ok_or_else(|| api::Error::Other(format!("Account {} has no password", account.name())))
 }
 
 pub struct Syspass {
     client: reqwest::blocking::Client,
-    request_number: Cell<u8>,
+    request_number: Cell<u32>,
     config: Config,
+    password: OnceCell<String>,
+    password_verified: Cell<bool>,
 }
 
 impl Syspass {
-    fn get_params(&self, args: RequestArguments, needs_password: bool) -> HashMap<String, String> {
-        let mut params: HashMap<String, String> = HashMap::from([("authToken".to_owned(), self.config.token.clone())]);
-
-        if needs_password {
-            if self.config.password.is_empty() {
-                params.insert("tokenPass".to_owned(), PASSWORD.to_string());
-            } else {
-                params.insert("tokenPass".to_owned(), self.config.password.clone());
-            }
+    // Also names the --record files, so ids must not repeat within a run
+    fn next_request_id(&self) -> u32 {
+        let id = self.request_number.get();
+        self.request_number.set(id.wrapping_add(1));
+
+        id
+    }
+
+    fn get_password(&self) -> Result<&String, api::Error> {
+        if let Some(password) = self.password.get() {
+            return Ok(password);
+        }
+
+        let password = credential::Source::from(&self.config).get_password(&self.config)?;
+
+        Ok(self.password.get_or_init(|| password))
+    }
+
+    // Called with the outcome of every request that sent the password, until the server has accepted or rejected it
+    fn verify_password(&self, error: Option<&api::Error>) {
+        let Some(password) = self.password.get() else {
+            return;
+        };
+        if self.password_verified.get() {
+            return;
+        }
+
+        match error {
+            None => credential::verified(&self.config, password, true),
+            Some(api::Error::Authentication(_)) => credential::verified(&self.config, password, false),
+            Some(_) => return,
+        }
+        self.password_verified.set(true);
+    }
+
+    fn get_params(&self, args: RequestArguments, needs_password: bool) -> Result<HashMap<String, Value>, api::Error> {
+        let mut params: HashMap<String, Value> =
+            HashMap::from([("authToken".to_owned(), Value::from(self.config.token.clone()))]);
+
+        if needs_password && self.config.replay.is_none() {
+            params.insert("tokenPass".to_owned(), Value::from(self.get_password()?.clone()));
         }
 
         if let Some(args) = args {
-            for arg in args {
-                if !arg.0.is_empty() && !arg.1.is_empty() {
-                    params.insert(arg.0.to_owned(), arg.1.clone());
+            for (key, value) in args {
+                if !key.is_empty() && !value.is_empty() {
+                    params.insert(key.to_owned(), to_param(key, value)?);
                 }
             }
         }
 
-        params
+        Ok(params)
     }
 
     fn send_request<T: DeserializeOwned>(&self, request_url: &str, req: &JsonReq) -> Result<T, api::Error> {
-        debug!("Sending request to {}:\n{:#?}\n", request_url, req);
+        if log_enabled!(Level::Debug) {
+            debug!(
+                "Sending request to {}:\n{:#}\n",
+                request_url,
+                redact(serde_json::to_value(req).unwrap_or_default())
+            );
+        }
 
-        match get_response(&self.client, request_url, req) {
-            Ok(result) => {
-                let json: Value = match result.json() {
-                    Ok(value) => value,
-                    Err(_) => return Err(api::Error("Server response did not contain JSON".to_string())),
-                };
+        let json: Value = match &self.config.replay {
+            Some(dir) => replay::load(dir, req)?,
+            None => get_response(&self.client, request_url, req)?
+                .json()
+                .map_err(|_| api::Error::MalformedResponse("Server response did not contain JSON".to_owned()))?,
+        };
 
-                debug!("Received response:\n{:#?}\n", json);
+        if log_enabled!(Level::Debug) {
+            debug!("Received response:\n{:#}\n", redact(json.clone()));
+        }
 
-                match serde_json::from_value::<T>(json) {
-                    Ok(result) => Ok(result),
-                    Err(error) => Err(api::Error(error.to_string())),
-                }
-            }
-            Err(error) => Err(error),
+        if let Some(dir) = &self.config.record {
+            replay::save(dir, req, &json)?;
         }
+
+        parse(json, "response")
     }
 }
 
@@ -116,22 +188,90 @@ impl From<Config> for Syspass {
             client: get_builder(&value).build().expect("Got client"),
             request_number: Cell::new(1),
             config: value,
+            password: OnceCell::new(),
+            password_verified: Cell::new(false),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::cell::Cell;
+    use std::cell::{Cell, OnceCell};
+    use std::fs;
+    use std::io::Write;
     use std::path::Path;
 
     use mockito::{Mock, ServerGuard};
     use passwords::PasswordGenerator;
+    use proptest::prelude::*;
     use reqwest::blocking::ClientBuilder;
-
+    use serde_json::{json, Value};
+    use tempfile::NamedTempFile;
+    use test_case::test_case;
+
+    use crate::api;
+    use crate::api::account::{Account, ChangePassword, EditAccount};
+    use crate::api::category::Category;
+    use crate::api::client::Client as SyspassClient;
     use crate::api::syspass::Syspass;
+    use crate::api::tag::Tag;
+    use crate::api::Api::{self, SyspassV2, SyspassV3};
     use crate::config::Config;
 
+    const FIELDS: [&str; 25] = [
+        "result",
+        "error",
+        "message",
+        "itemId",
+        "resultCode",
+        "id",
+        "name",
+        "login",
+        "categoryId",
+        "clientId",
+        "password",
+        "pass",
+        "account_id",
+        "account_name",
+        "account_login",
+        "account_categoryId",
+        "account_customerId",
+        "account_pass",
+        "customer_id",
+        "customer_name",
+        "category_id",
+        "category_name",
+        "passDateChange",
+        "tagsId",
+        "xml",
+    ];
+
+    // Every request whose response is parsed, named like the malformed fixtures
+    const REQUESTS: [&str; 22] = [
+        "account_search",
+        "account_view_password",
+        "account_add",
+        "account_edit_password",
+        "account_edit",
+        "account_delete",
+        "account_expiring",
+        "view_account",
+        "category_list",
+        "category_view",
+        "category_save",
+        "category_delete",
+        "client_list",
+        "client_view",
+        "client_save",
+        "client_delete",
+        "tag_list",
+        "tag_view",
+        "tag_save",
+        "tag_delete",
+        "backup",
+        "export",
+    ];
+
     pub fn create_server_response(
         response: Option<impl AsRef<Path>>,
         status: usize,
@@ -163,11 +303,154 @@ mod tests {
             password_timeout: None,
             no_clipboard: false,
             no_shell: false,
+            ..Default::default()
         });
 
         (response.0, client, response.1)
     }
 
+    fn any_json() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            "[0-9]{1,3}".prop_map(Value::from),
+            "[a-z -]{0,8}".prop_map(Value::from),
+        ];
+        let key = prop_oneof![prop::sample::select(&FIELDS[..]).prop_map(ToOwned::to_owned), "[0-9]"];
+
+        leaf.prop_recursive(4, 32, 6, move |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+                prop::collection::btree_map(key.clone(), inner, 0..6).prop_map(Value::from_iter),
+            ]
+        })
+    }
+
+    // Wraps random values in the envelopes of both API versions so the entity conversions are reached
+    fn any_response() -> impl Strategy<Value = Value> {
+        prop_oneof![
+            any_json(),
+            any_json().prop_map(|result| json!({"jsonrpc": "2.0", "id": 1, "result": result})),
+            any_json().prop_map(|result| {
+                json!({"jsonrpc": "2.0", "id": 1, "result": {"itemId": 1, "resultCode": 0, "result": result}})
+            }),
+        ]
+    }
+
+    fn create_api_client(response: &Path, api: &Api) -> (Box<dyn api::Client>, ServerGuard) {
+        let (_, server) = crate::tests::create_server_response(Some(response), 200, "POST", "/api.php");
+        let client = api.get(Config {
+            host: server.url() + "/api.php",
+            token: "1234".to_owned(),
+            password: "<PASSWORD>".to_owned(),
+            ..Default::default()
+        });
+
+        (client, server)
+    }
+
+    fn test_account(id: Option<u32>) -> Account {
+        Account::new(
+            id,
+            "name".to_owned(),
+            "login".to_owned(),
+            None,
+            None,
+            1,
+            1,
+            Some("password".to_owned()),
+            None,
+        )
+    }
+
+    fn call(client: &dyn api::Client, fixture: &str) -> Result<(), api::Error> {
+        match fixture.split_once('.').map_or(fixture, |(name, _)| name) {
+            name if name.starts_with("account_search") => client.search_account(vec![], false).map(|_| ()),
+            name if name.starts_with("account_view_password") => {
+                client.get_password(&test_account(Some(1))).map(|_| ())
+            }
+            name if name.starts_with("account_add") => client.save_account(&test_account(None)).map(|_| ()),
+            name if name.starts_with("view_account") => client.view_account(1).map(|_| ()),
+            name if name.starts_with("category_list") => client.get_categories().map(|_| ()),
+            name if name.starts_with("client_list") => client.get_clients().map(|_| ()),
+            name if name.starts_with("client_view") => client.get_client(1).map(|_| ()),
+            name if name.starts_with("client_save") => client.save_client(&SyspassClient::default()).map(|_| ()),
+            name if name.starts_with("client_delete") => client.delete_client(1).map(|_| ()),
+            name if name.starts_with("category_view") => client.get_category(1).map(|_| ()),
+            name if name.starts_with("category_save") => client.save_category(&Category::default()).map(|_| ()),
+            name if name.starts_with("category_delete") => client.delete_category(1).map(|_| ()),
+            name if name.starts_with("tag_list") => client.get_tags().map(|_| ()),
+            name if name.starts_with("tag_view") => client.get_tag(1).map(|_| ()),
+            name if name.starts_with("tag_save") => client.save_tag(&Tag::default()).map(|_| ()),
+            name if name.starts_with("tag_delete") => client.delete_tag(1).map(|_| ()),
+            name if name.starts_with("account_edit_password") => client
+                .change_password(&ChangePassword {
+                    pass: "password".to_owned(),
+                    id: 1,
+                    expire_date: 0,
+                })
+                .map(|_| ()),
+            name if name.starts_with("account_edit") => client
+                .edit_account(&EditAccount {
+                    id: 1,
+                    name: "name".to_owned(),
+                    category_id: 1,
+                    client_id: 1,
+                    login: "login".to_owned(),
+                    url: String::new(),
+                    notes: String::new(),
+                    tags_id: vec![1],
+                    expire_date: 0,
+                })
+                .map(|_| ()),
+            name if name.starts_with("account_delete") => client.delete_account(1).map(|_| ()),
+            name if name.starts_with("account_expiring") => client.get_expiring_accounts().map(|_| ()),
+            name if name.starts_with("backup") => client.run_backup(Some("/tmp")).map(|_| ()),
+            name if name.starts_with("export") => client.run_export(Some("password"), None).map(|_| ()),
+            name => panic!("No request for fixture {name}"),
+        }
+    }
+
+    #[test_case(&SyspassV2, "tests/responses/syspass/v2/malformed")]
+    #[test_case(&SyspassV3, "tests/responses/syspass/v3/malformed")]
+    fn test_malformed_responses(api: &Api, dir: &str) {
+        let mut fixtures = fs::read_dir(dir)
+            .expect("Failed to read fixtures")
+            .map(|entry| entry.expect("Failed to read fixture").path())
+            .collect::<Vec<_>>();
+        fixtures.sort();
+        assert!(!fixtures.is_empty());
+
+        for fixture in fixtures {
+            let name = fixture.file_name().expect("Fixture has a name").to_string_lossy();
+            let (client, _server) = create_api_client(&fixture, api);
+            let result = call(client.as_ref(), &name);
+
+            assert!(
+                matches!(result, Err(api::Error::MalformedResponse(_))),
+                "{name} should be reported as malformed, got {result:?}"
+            );
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(48))]
+
+        #[test]
+        fn test_any_response_does_not_panic(response in any_response()) {
+            let mut file = NamedTempFile::new().expect("Failed to create file");
+            file.write_all(response.to_string().as_bytes()).expect("Failed to write file");
+
+            for api in [SyspassV2, SyspassV3] {
+                let (client, _server) = create_api_client(file.path(), &api);
+                for request in REQUESTS {
+                    let _ = call(client.as_ref(), request);
+                }
+            }
+        }
+    }
+
     #[test]
     pub fn test_get_params() {
         let syspass = Syspass {
@@ -178,31 +461,67 @@ mod tests {
                 token: "test_token".to_owned(),
                 ..Default::default()
             },
+            password: OnceCell::new(),
+            password_verified: Cell::new(false),
         };
 
-        let params = syspass.get_params(Some(vec![("id", "some id".to_owned())]), false);
+        let params = syspass
+            .get_params(Some(vec![("id", "some id".to_owned())]), false)
+            .expect("Failed to get params");
 
-        assert_eq!("some id", params.get("id").expect("Failed to find id").as_str());
+        assert_eq!(
+            "some id",
+            params.get("id").and_then(Value::as_str).expect("Failed to find id")
+        );
 
         assert_eq!(
             "test_token",
-            params.get("authToken").expect("Failed to find token").as_str()
+            params
+                .get("authToken")
+                .and_then(Value::as_str)
+                .expect("Failed to find token")
         );
 
         assert_eq!(None, params.get("tokenPass"));
 
-        let params = syspass.get_params(Some(vec![("id", "some id".to_owned())]), true);
+        let params = syspass
+            .get_params(Some(vec![("id", "some id".to_owned())]), true)
+            .expect("Failed to get params");
 
-        assert_eq!("some id", params.get("id").expect("Failed to find id").as_str());
+        assert_eq!(
+            "some id",
+            params.get("id").and_then(Value::as_str).expect("Failed to find id")
+        );
 
         assert_eq!(
             "test_token",
-            params.get("authToken").expect("Failed to find token").as_str()
+            params
+                .get("authToken")
+                .and_then(Value::as_str)
+                .expect("Failed to find token")
         );
 
         assert_eq!(
             "test_password",
-            params.get("tokenPass").expect("Failed to find password").as_str()
+            params
+                .get("tokenPass")
+                .and_then(Value::as_str)
+                .expect("Failed to find password")
         );
     }
+
+    #[test]
+    fn test_get_params_id_list() {
+        let syspass = Syspass::from(Config::default());
+
+        let params = syspass
+            .get_params(Some(vec![("tagsId", "4,7".to_owned()), ("id", "1".to_owned())]), false)
+            .expect("Failed to get params");
+
+        assert_eq!(Some(&json!([4, 7])), params.get("tagsId"));
+        assert_eq!(Some(&json!("1")), params.get("id"));
+        assert!(syspass
+            .get_params(Some(vec![("tagsId", "tag".to_owned())]), false)
+            .is_err());
+    }
 }
@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::api;
+use crate::api::syspass::JsonReq;
+use crate::redact::mask;
+
+// Exchanges are numbered by request id, so one directory holds the traffic of a single run
+fn get_file_name(req: &JsonReq) -> String {
+    format!("{:03}-{}", req.id, req.method.replace('/', "_"))
+}
+
+fn get_response_file(dir: &Path, req: &JsonReq) -> PathBuf {
+    dir.join(get_file_name(req) + ".json")
+}
+
+pub fn save(dir: &Path, req: &JsonReq, response: &Value) -> Result<(), api::Error> {
+    let write = |path: PathBuf, value: Value| {
+        serde_json::to_string_pretty(&mask(value))
+            .map_err(std::io::Error::other)
+            .and_then(|data| fs::write(&path, data + "\n"))
+            .map_err(|error| api::Error::Other(format!("Could not record to {}: {error}", path.display())))
+    };
+
+    fs::create_dir_all(dir)
+        .map_err(|error| api::Error::Other(format!("Could not create {}: {error}", dir.display())))?;
+
+    write(
+        dir.join(get_file_name(req) + ".request.json"),
+        serde_json::to_value(req).unwrap_or_default(),
+    )?;
+    write(get_response_file(dir, req), response.clone())
+}
+
+pub fn load(dir: &Path, req: &JsonReq) -> Result<Value, api::Error> {
+    let path = get_response_file(dir, req);
+    let data = fs::read_to_string(&path)
+        .map_err(|error| api::Error::Other(format!("No recorded response in {}: {error}", path.display())))?;
+
+    serde_json::from_str(&data).map_err(|error| api::Error::MalformedResponse(format!("{}: {error}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use crate::api::account::Account;
+    use crate::api::syspass::replay::{load, save};
+    use crate::api::syspass::JsonReq;
+    use crate::api::Api;
+    use crate::config::Config;
+
+    fn request(id: u32) -> JsonReq {
+        JsonReq {
+            jsonrpc: "2.0".to_owned(),
+            method: "account/viewPass".to_owned(),
+            params: HashMap::from([("authToken".to_owned(), json!("token")), ("id".to_owned(), json!("1"))]),
+            id,
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": {"result": {"password": "secret"}}});
+
+        save(dir.path(), &request(1), &response).expect("Failed to record");
+
+        let recorded: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(dir.path().join("001-account_viewPass.request.json"))
+                .expect("Request was not recorded"),
+        )
+        .expect("Invalid JSON");
+        assert_eq!(json!("********"), recorded["params"]["authToken"]);
+        assert_eq!(json!("1"), recorded["params"]["id"]);
+
+        assert_eq!(
+            json!({"jsonrpc": "2.0", "id": 1, "result": {"result": {"password": "********"}}}),
+            load(dir.path(), &request(1)).expect("Failed to replay")
+        );
+        assert!(load(dir.path(), &request(2)).is_err());
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let (mock, server) = crate::tests::create_server_response(
+            Some("tests/responses/syspass/v3/accounts_search_results.json"),
+            200,
+            "POST",
+            "/api.php",
+        );
+
+        let recorded = Api::SyspassV3
+            .get(Config {
+                host: server.url() + "/api.php",
+                record: Some(dir.path().to_path_buf()),
+                ..Default::default()
+            })
+            .search_account(vec![("text", "test".to_owned())], false)
+            .expect("Search should succeed");
+        mock.assert();
+        drop(server);
+
+        let replayed = Api::SyspassV3
+            .get(Config {
+                replay: Some(dir.path().to_path_buf()),
+                ..Default::default()
+            })
+            .search_account(vec![("text", "test".to_owned())], false)
+            .expect("Replay should succeed");
+
+        assert_eq!(
+            recorded.iter().map(Account::name).collect::<Vec<_>>(),
+            replayed.iter().map(Account::name).collect::<Vec<_>>()
+        );
+        assert!(dir.path().join("001-account_search.json").exists());
+    }
+
+    #[test]
+    fn test_record_and_replay_many_requests() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let (_mock, server) = crate::tests::create_server_response(
+            Some("tests/responses/syspass/v3/accounts_search_results.json"),
+            200,
+            "POST",
+            "/api.php",
+        );
+
+        let recorder = Api::SyspassV3.get(Config {
+            host: server.url() + "/api.php",
+            record: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        });
+        for _ in 0..300 {
+            recorder
+                .search_account(vec![("text", "test".to_owned())], false)
+                .expect("Search should succeed");
+        }
+        drop(server);
+
+        assert_eq!(600, fs::read_dir(dir.path()).expect("Failed to read records").count());
+        assert!(dir.path().join("300-account_search.json").exists());
+
+        let replayer = Api::SyspassV3.get(Config {
+            replay: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        });
+        for _ in 0..300 {
+            replayer
+                .search_account(vec![("text", "test".to_owned())], false)
+                .expect("Replay should succeed");
+        }
+    }
+}
@@ -1,13 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::api;
-use crate::api::account::{ChangePassword, ViewPassword};
+use crate::api::account::{ChangePassword, EditAccount, ViewPassword};
 use crate::api::entity::Entity;
-use crate::api::syspass::{sort_accounts, JsonReq, RequestArguments};
+use crate::api::syspass::{get_account_id, get_account_pass, parse, sort_accounts, JsonReq, RequestArguments};
+use crate::api::tag::Tag;
 use crate::config::Config;
 
 // https://syspass-doc.readthedocs.io/en/2.1/application/api.html
@@ -27,7 +28,7 @@ struct ApiResponse {
 
 #[derive(Deserialize, Debug, Serialize)]
 struct ApiResponseEntity {
-    id: u8,
+    id: u32,
     jsonrpc: String,
     result: Value,
     error: Option<ApiErrorResponse>,
@@ -47,14 +48,16 @@ struct Client {
     customer_name: String,
 }
 
-impl From<Client> for api::client::Client {
-    fn from(value: Client) -> Self {
-        Self::new(
-            Some(value.customer_id.parse::<u32>().expect("Customer id is required")),
-            value.customer_name.clone(),
-            value.customer_description.clone(),
+impl TryFrom<Client> for api::client::Client {
+    type Error = api::Error;
+
+    fn try_from(value: Client) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            Some(parse_id(&value.customer_id, "customer_id")?),
+            value.customer_name,
+            value.customer_description,
             0,
-        )
+        ))
     }
 }
 
@@ -65,6 +68,18 @@ enum ApiResponseResult {
     Entity(ApiResponseEntity),
 }
 
+impl ApiResponseResult {
+    // Errors are sent without an entity, so they always end up as a result code
+    fn into_entity(self) -> Result<ApiResponseEntity, api::Error> {
+        match self {
+            Self::Entity(ApiResponseEntity { error: Some(error), .. })
+            | Self::Code(ApiResponse { error: Some(error), .. }) => Err(api::Error::from_server_message(error.message)),
+            Self::Entity(result) => Ok(result),
+            Self::Code(response) => Err(api::Error::MalformedResponse(format!("{response:?}"))),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[allow(non_snake_case, clippy::struct_field_names)]
 struct Account {
@@ -88,33 +103,56 @@ struct Category {
     category_name: String,
 }
 
-impl From<Category> for api::category::Category {
-    fn from(value: Category) -> Self {
-        Self::new(
-            Some(value.category_id.parse::<u32>().expect("Category id is required")),
-            value.category_name.clone(),
-            value.category_description.clone(),
-        )
+impl TryFrom<Category> for api::category::Category {
+    type Error = api::Error;
+
+    fn try_from(value: Category) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            Some(parse_id(&value.category_id, "category_id")?),
+            value.category_name,
+            value.category_description,
+        ))
     }
 }
 
-impl From<Account> for api::account::Account {
-    fn from(value: Account) -> Self {
-        Self::new(
-            Some(value.account_id.parse::<u32>().expect("Account id is required")),
-            value.account_name.clone(),
-            value.account_login.clone(),
-            value.account_url.clone(),
-            value.account_notes.clone(),
-            value.account_categoryId.parse().expect("Category id is required"),
-            value.account_customerId.parse().expect("Customer id id is required"),
-            Some(value.account_pass.clone()),
-            Some(value.customer_name.clone()),
-        )
+impl TryFrom<Account> for api::account::Account {
+    type Error = api::Error;
+
+    fn try_from(value: Account) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            Some(parse_id(&value.account_id, "account_id")?),
+            value.account_name,
+            value.account_login,
+            value.account_url,
+            value.account_notes,
+            parse_id(&value.account_categoryId, "account_categoryId")?,
+            parse_id(&value.account_customerId, "account_customerId")?,
+            Some(value.account_pass),
+            Some(value.customer_name),
+        ))
     }
 }
 
-const NOT_SUPPORTED: &str = "Syspass does not support this";
+// v2 sends every number as a string
+fn parse_id(value: &str, field: &str) -> Result<u32, api::Error> {
+    value
+        .parse()
+        .map_err(|_| api::Error::MalformedResponse(format!("{field}: expected an id, got {value:?}")))
+}
+
+fn parse_item_id(value: Option<&Value>) -> Result<u32, api::Error> {
+    match value {
+        Some(Value::String(value)) => parse_id(value, "itemId"),
+        Some(Value::Number(value)) => value
+            .as_u64()
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or_else(|| api::Error::MalformedResponse(format!("itemId: expected an id, got {value}"))),
+        Some(value) => Err(api::Error::MalformedResponse(format!(
+            "itemId: expected an id, got {value}"
+        ))),
+        None => Err(api::Error::MalformedResponse("itemId is missing".to_owned())),
+    }
+}
 
 impl Syspass {
     fn forge_and_send(
@@ -123,23 +161,30 @@ impl Syspass {
         args: RequestArguments,
         needs_password: bool,
     ) -> Result<ApiResponseResult, api::Error> {
-        let params = self.syspass.get_params(args, needs_password);
+        let params = self.syspass.get_params(args, needs_password)?;
         let req = JsonReq {
             jsonrpc: String::from("2.0"),
             method: method.to_owned(),
             params,
-            id: self.syspass.request_number.get(),
+            id: self.syspass.next_request_id(),
         };
         let response = self
             .syspass
             .send_request::<ApiResponseResult>(&self.syspass.config.host, &req);
 
-        self.syspass.request_number.set(self.syspass.request_number.get() + 1);
-
-        match response {
-            Ok(response) => Ok(response),
-            Err(err) => Err(api::Error(err.to_string())),
+        if needs_password {
+            let server_error = match &response {
+                Ok(
+                    ApiResponseResult::Code(ApiResponse { error: Some(error), .. })
+                    | ApiResponseResult::Entity(ApiResponseEntity { error: Some(error), .. }),
+                ) => Some(api::Error::from_server_message(error.message.clone())),
+                _ => None,
+            };
+            self.syspass
+                .verify_password(server_error.as_ref().or_else(|| response.as_ref().err()));
         }
+
+        response
     }
 
     fn delete_request(&self, method: &str, id: u32) -> Result<bool, api::Error> {
@@ -147,11 +192,15 @@ impl Syspass {
             Ok(result) => {
                 if let ApiResponseResult::Code(result) = result {
                     match result.error {
-                        Some(error) => Err(api::Error(error.message)),
-                        _ => Ok(result.result.expect("Invalid response").result_code == 0),
+                        Some(error) => Err(api::Error::from_server_message(error.message)),
+                        _ => Ok(result
+                            .result
+                            .ok_or_else(|| api::Error::MalformedResponse("result is missing".to_owned()))?
+                            .result_code
+                            == 0),
                     }
                 } else {
-                    Err(api::Error("Save failed".to_owned()))
+                    Err(api::Error::MalformedResponse("Expected a result code".to_owned()))
                 }
             }
             Err(error) => Err(error),
@@ -161,28 +210,27 @@ impl Syspass {
     fn save(&self, path: &str, id: Option<&u32>, args: RequestArguments) -> Result<u32, api::Error> {
         if let Some(new_id) = id {
             if *new_id > 0 {
-                return Err(api::Error(NOT_SUPPORTED.to_owned()));
+                return Err(api::Error::Unsupported);
             }
         }
 
         match self.forge_and_send(path, args, true) {
             Ok(result) => match result {
-                ApiResponseResult::Code(result) => match result.error {
-                    Some(error) => Err(api::Error(error.message)),
-                    _ => Ok(result
-                        .result
-                        .expect("Invalid response")
-                        .item_id
-                        .expect("Entity was not created")
-                        .parse::<u32>()
-                        .expect("Invalid id")),
+                ApiResponseResult::Code(result) => match (result.result, result.error) {
+                    (_, Some(error)) => Err(api::Error::from_server_message(error.message)),
+                    (
+                        Some(ApiResult {
+                            item_id: Some(item_id), ..
+                        }),
+                        None,
+                    ) => parse_id(&item_id, "itemId"),
+                    (_, None) => Err(api::Error::MalformedResponse("itemId is missing".to_owned())),
                 },
                 ApiResponseResult::Entity(result) => {
                     if let Some(error) = result.error {
-                        Err(api::Error(error.message))
+                        Err(api::Error::from_server_message(error.message))
                     } else {
-                        let item_id = result.result.get("itemId").expect("No password set").to_string();
-                        Ok(item_id.parse::<u32>().expect("Invalid id"))
+                        parse_item_id(result.result.get("itemId"))
                     }
                 }
             },
@@ -190,13 +238,16 @@ impl Syspass {
         }
     }
 
-    fn fix_result_object<T: DeserializeOwned>(result: &Value) -> Vec<T> {
+    // Lists are returned as an object keyed by index, next to non-numeric metadata keys
+    fn fix_result_object<T: DeserializeOwned>(result: Value, entity: &str) -> Result<Vec<T>, api::Error> {
+        let Value::Object(result) = result else {
+            return Err(api::Error::MalformedResponse(format!("{entity}: expected an object")));
+        };
+
         result
-            .as_object()
-            .expect("Failed to map result")
-            .iter()
+            .into_iter()
             .filter(|(key, _val)| key.parse::<u32>().is_ok())
-            .map(|(_key, value)| serde_json::from_value::<T>(value.clone()).expect("Failed to convert entity list"))
+            .map(|(key, value)| parse::<T>(value, &format!("{entity}[{key}]")))
             .collect()
     }
 }
@@ -219,27 +270,28 @@ impl api::Client for Syspass {
         search: Vec<(&str, String)>,
         usage: bool,
     ) -> Result<Vec<api::account::Account>, api::Error> {
+        // v2 has no tags and would ignore the filter, returning every match as if it applied
+        if search.iter().any(|(key, _)| *key == "tagsId") {
+            return Err(api::Error::Unsupported);
+        }
+
         match self.forge_and_send("getAccountSearch", Some(search), false) {
-            Ok(response) => match response {
-                ApiResponseResult::Entity(result) => {
-                    let mut list: Vec<api::account::Account> = vec![];
-                    let convert_list: Vec<Account> = serde_json::from_value(result.result).expect("Invalid response");
-                    for account in convert_list {
-                        list.push(api::account::Account::from(account));
-                    }
+            Ok(response) => {
+                let mut list = parse::<Vec<Account>>(response.into_entity()?.result, "account list")?
+                    .into_iter()
+                    .map(api::account::Account::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                    let usage_data: HashMap<u32, u32> = if usage {
-                        Config::get_usage_data(None)
-                    } else {
-                        HashMap::from([(0, 0)])
-                    };
+                let usage_data: HashMap<u32, u32> = if usage {
+                    Config::get_usage_data(self.syspass.config.profile.as_deref(), None)
+                } else {
+                    HashMap::from([(0, 0)])
+                };
 
-                    sort_accounts(&mut list, &usage_data);
+                sort_accounts(&mut list, &usage_data);
 
-                    Ok(list)
-                }
-                ApiResponseResult::Code(_) => Err(api::Error(format!("Invalid response: {response:?}"))),
-            },
+                Ok(list)
+            }
             Err(error) => Err(error),
         }
     }
@@ -247,25 +299,19 @@ impl api::Client for Syspass {
     fn get_password(&self, account: &api::account::Account) -> Result<ViewPassword, api::Error> {
         match self.forge_and_send(
             "getAccountPassword",
-            Some(vec![("id", account.id().expect("Should not be empty").to_string())]),
+            Some(vec![("id", get_account_id(account)?.to_string())]),
             true,
         ) {
-            Ok(response) => {
-                if let ApiResponseResult::Entity(result) = response {
-                    Ok(ViewPassword {
-                        account: account.clone(),
-                        password: result
-                            .result
-                            .get("pass")
-                            .expect("No password set")
-                            .as_str()
-                            .expect("Failed get password reference")
-                            .to_owned(),
-                    })
-                } else {
-                    Err(api::Error(format!("Invalid response {response:?}")))
-                }
-            }
+            Ok(response) => Ok(ViewPassword {
+                account: account.clone(),
+                password: response
+                    .into_entity()?
+                    .result
+                    .get("pass")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| api::Error::MalformedResponse("pass is missing".to_owned()))?
+                    .to_owned(),
+            }),
             Err(error) => Err(error),
         }
     }
@@ -273,16 +319,12 @@ impl api::Client for Syspass {
     fn get_clients(&self) -> Result<Vec<api::client::Client>, api::Error> {
         match self.forge_and_send("getCustomers", None, false) {
             Ok(response) => {
-                let mut list: Vec<api::client::Client> = vec![];
-                if let ApiResponseResult::Entity(result) = response {
-                    let convert_list: Vec<Client> = Self::fix_result_object::<Client>(&result.result);
+                let mut list = Self::fix_result_object::<Client>(response.into_entity()?.result, "client list")?
+                    .into_iter()
+                    .map(api::client::Client::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                    for client in convert_list {
-                        list.push(api::client::Client::from(client));
-                    }
-
-                    list.sort_by(|a, b| a.id().cmp(&b.id()));
-                }
+                list.sort_by(|a, b| a.id().cmp(&b.id()));
                 Ok(list)
             }
             Err(error) => Err(error),
@@ -292,16 +334,12 @@ impl api::Client for Syspass {
     fn get_categories(&self) -> Result<Vec<api::category::Category>, api::Error> {
         match self.forge_and_send("getCategories", None, false) {
             Ok(response) => {
-                let mut list: Vec<api::category::Category> = vec![];
-                if let ApiResponseResult::Entity(result) = response {
-                    let convert_list: Vec<Category> = Self::fix_result_object::<Category>(&result.result);
+                let mut list = Self::fix_result_object::<Category>(response.into_entity()?.result, "category list")?
+                    .into_iter()
+                    .map(api::category::Category::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                    for category in convert_list {
-                        list.push(api::category::Category::from(category));
-                    }
-
-                    list.sort_by(|a, b| a.id().cmp(&b.id()));
-                }
+                list.sort_by(|a, b| a.id().cmp(&b.id()));
                 Ok(list)
             }
             Err(error) => Err(error),
@@ -357,7 +395,7 @@ impl api::Client for Syspass {
                 ("name", account.name().to_owned()),
                 ("categoryId", account.category_id().to_string()),
                 ("customerId", account.client_id().to_string()),
-                ("pass", account.pass().expect("Password given").to_owned()),
+                ("pass", get_account_pass(account)?.to_owned()),
                 ("login", account.login().to_owned()),
                 ("url", account.url().unwrap_or_default().to_owned()),
                 ("notes", account.notes().unwrap_or_default().to_owned()),
@@ -371,7 +409,15 @@ impl api::Client for Syspass {
     }
 
     fn change_password(&self, _password: &ChangePassword) -> Result<api::account::Account, api::Error> {
-        Err(api::Error(NOT_SUPPORTED.to_owned()))
+        Err(api::Error::Unsupported)
+    }
+
+    fn can_change_password(&self) -> bool {
+        false
+    }
+
+    fn edit_account(&self, _account: &EditAccount) -> Result<api::account::Account, api::Error> {
+        Err(api::Error::Unsupported)
     }
 
     fn delete_client(&self, id: u32) -> Result<bool, api::Error> {
@@ -388,26 +434,47 @@ impl api::Client for Syspass {
 
     fn view_account(&self, id: u32) -> Result<api::account::Account, api::Error> {
         match self.forge_and_send("getAccountData", Some(vec![("id", id.to_string())]), true) {
-            Ok(response) => match response {
-                ApiResponseResult::Entity(result) => Ok(api::account::Account::from({
-                    let result = serde_json::from_value::<Account>(result.result);
-                    match result {
-                        Ok(account) => account,
-                        Err(error) => return Err(api::Error(format!("{error}: Could not get account data"))),
-                    }
-                })),
-                ApiResponseResult::Code(_) => Err(api::Error(format!("Invalid response: {response:?}"))),
-            },
+            Ok(response) => {
+                api::account::Account::try_from(parse::<Account>(response.into_entity()?.result, "account")?)
+            }
             Err(error) => Err(error),
         }
     }
 
     fn get_category(&self, _id: u32) -> Result<api::category::Category, api::Error> {
-        Err(api::Error(NOT_SUPPORTED.to_owned()))
+        Err(api::Error::Unsupported)
     }
 
     fn get_client(&self, _id: u32) -> Result<api::client::Client, api::Error> {
-        Err(api::Error(NOT_SUPPORTED.to_owned()))
+        Err(api::Error::Unsupported)
+    }
+
+    fn get_tags(&self) -> Result<Vec<Tag>, api::Error> {
+        Err(api::Error::Unsupported)
+    }
+
+    fn get_tag(&self, _id: u32) -> Result<Tag, api::Error> {
+        Err(api::Error::Unsupported)
+    }
+
+    fn save_tag(&self, _tag: &Tag) -> Result<Tag, api::Error> {
+        Err(api::Error::Unsupported)
+    }
+
+    fn delete_tag(&self, _id: u32) -> Result<bool, api::Error> {
+        Err(api::Error::Unsupported)
+    }
+
+    fn run_backup(&self, _path: Option<&str>) -> Result<BTreeMap<String, String>, api::Error> {
+        Err(api::Error::Unsupported)
+    }
+
+    fn run_export(&self, _password: Option<&str>, _path: Option<&str>) -> Result<BTreeMap<String, String>, api::Error> {
+        Err(api::Error::Unsupported)
+    }
+
+    fn get_expiring_accounts(&self) -> Result<Vec<api::account::Account>, api::Error> {
+        Err(api::Error::Unsupported)
     }
 
     fn get_config(&self) -> &Config {
@@ -423,9 +490,10 @@ mod tests {
     use test_case::test_case;
 
     use crate::api;
-    use crate::api::account::ChangePassword;
+    use crate::api::account::{ChangePassword, EditAccount};
     use crate::api::entity::Entity;
-    use crate::api::syspass::v2::{Account, Category, Client, Syspass, NOT_SUPPORTED};
+    use crate::api::syspass::v2::{Account, Category, Client, Syspass};
+    use crate::api::tag::Tag;
     use crate::api::Client as ApiClient;
     use crate::config::Config;
 
@@ -445,6 +513,7 @@ mod tests {
             password_timeout: None,
             no_clipboard: false,
             no_shell: false,
+            ..Default::default()
         })
     }
 
@@ -467,7 +536,11 @@ mod tests {
         let response = test.1.search_account(vec![], false);
         assert!(response.is_err());
         let search = format!("Server responded with code {status}");
-        assert!(response.err().expect("Err was not set").0.contains(search.as_str()));
+        assert!(response
+            .err()
+            .expect("Err was not set")
+            .to_string()
+            .contains(search.as_str()));
 
         test.0.assert();
     }
@@ -483,7 +556,11 @@ mod tests {
 
         assert!(accounts.is_err());
         let search = format!("Server responded with code {status}");
-        assert!(accounts.err().expect("Err was not set").0.contains(search.as_str()));
+        assert!(accounts
+            .err()
+            .expect("Err was not set")
+            .to_string()
+            .contains(search.as_str()));
 
         test.0.assert();
     }
@@ -531,11 +608,27 @@ mod tests {
             password_timeout: None,
             no_clipboard: false,
             no_shell: false,
+            ..Default::default()
         });
 
         assert!(client.search_account(vec![], false).is_err());
     }
 
+    #[test]
+    fn test_run_backup_and_export() {
+        let client = get_test_client(String::new());
+
+        assert!(matches!(client.run_backup(None), Err(api::Error::Unsupported)));
+        assert!(matches!(client.run_export(None, None), Err(api::Error::Unsupported)));
+    }
+
+    #[test]
+    fn test_get_expiring_accounts() {
+        let client = get_test_client(String::new());
+
+        assert!(matches!(client.get_expiring_accounts(), Err(api::Error::Unsupported)));
+    }
+
     #[test]
     fn test_change_account_password() {
         let client = get_test_client(String::new());
@@ -547,7 +640,28 @@ mod tests {
 
         assert!(client
             .change_password(&change)
-            .is_err_and(|error| NOT_SUPPORTED == error.to_string()));
+            .is_err_and(|error| matches!(error, api::Error::Unsupported)));
+        assert!(!client.can_change_password());
+    }
+
+    #[test]
+    fn test_edit_account() {
+        let client = get_test_client(String::new());
+        let edit = EditAccount {
+            id: 1,
+            name: "name".to_owned(),
+            category_id: 1,
+            client_id: 1,
+            login: String::new(),
+            url: String::new(),
+            notes: String::new(),
+            tags_id: vec![],
+            expire_date: 0,
+        };
+
+        assert!(client
+            .edit_account(&edit)
+            .is_err_and(|error| matches!(error, api::Error::Unsupported)));
     }
 
     #[test]
@@ -603,7 +717,7 @@ mod tests {
             customer_name: "Customer name".to_owned(),
         };
 
-        let converted = api::client::Client::from(client.clone());
+        let converted = api::client::Client::try_from(client.clone()).expect("Client should convert");
 
         assert_eq!(
             client.customer_description.unwrap_or_default(),
@@ -624,7 +738,7 @@ mod tests {
             category_name: "Category name".to_owned(),
         };
 
-        let converted = api::category::Category::from(category.clone());
+        let converted = api::category::Category::try_from(category.clone()).expect("Category should convert");
 
         assert_eq!(
             category.category_description.unwrap_or_default(),
@@ -656,7 +770,7 @@ mod tests {
             customer_name: "customer".to_owned(),
         };
 
-        let converted = api::account::Account::from(account.clone());
+        let converted = api::account::Account::try_from(account.clone()).expect("Account should convert");
 
         assert_eq!(
             account.account_categoryId.parse::<u32>().expect("Failed to read id"),
@@ -719,13 +833,33 @@ mod tests {
     #[test]
     fn test_get_client() {
         let client = get_test_client(String::new());
-        assert!(client.get_client(1).is_err_and(|e| NOT_SUPPORTED == e.to_string()));
+        assert!(client
+            .get_client(1)
+            .is_err_and(|e| matches!(e, api::Error::Unsupported)));
     }
 
     #[test]
     fn test_get_category() {
         let client = get_test_client(String::new());
-        assert!(client.get_category(1).is_err_and(|e| NOT_SUPPORTED == e.to_string()));
+        assert!(client
+            .get_category(1)
+            .is_err_and(|e| matches!(e, api::Error::Unsupported)));
+    }
+
+    #[test]
+    fn test_tags_not_supported() {
+        let client = get_test_client(String::new());
+        assert!(client.get_tags().is_err_and(|e| matches!(e, api::Error::Unsupported)));
+        assert!(client.get_tag(1).is_err_and(|e| matches!(e, api::Error::Unsupported)));
+        assert!(client
+            .delete_tag(1)
+            .is_err_and(|e| matches!(e, api::Error::Unsupported)));
+        assert!(client
+            .save_tag(&Tag::default())
+            .is_err_and(|e| matches!(e, api::Error::Unsupported)));
+        assert!(client
+            .search_account(vec![("tagsId", "1".to_owned())], false)
+            .is_err_and(|e| matches!(e, api::Error::Unsupported)));
     }
 
     #[test]
@@ -753,9 +887,7 @@ mod tests {
         let account = get_test_account();
         let result = test.1.save_account(&account);
 
-        assert!(
-            result.is_err_and(|x| x.to_string() == "missing field `account_categoryId`: Could not get account data")
-        );
+        assert!(result.is_err_and(|x| x.to_string() == "Invalid response: account: missing field `account_categoryId`"));
     }
 
     #[test]
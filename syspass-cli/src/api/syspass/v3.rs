@@ -1,15 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::Value;
 
 use crate::api;
-use crate::api::account::{Account, ChangePassword, ViewPassword};
+use crate::api::account::{Account, ChangePassword, EditAccount, ViewPassword};
 use crate::api::category::Category;
 use crate::api::client::Client;
 use crate::api::entity::Entity;
-use crate::api::syspass::{sort_accounts, JsonReq, RequestArguments, Syspass as SyspassShared};
+use crate::api::syspass::{
+    get_account_id, get_account_pass, parse, sort_accounts, JsonReq, RequestArguments, Syspass as SyspassShared,
+};
+use crate::api::tag::Tag;
 use crate::config::Config;
 
 // https://syspass-doc.readthedocs.io/en/3.1/application/api.html
@@ -43,22 +46,29 @@ impl Syspass {
         args: RequestArguments,
         needs_password: bool,
     ) -> Result<ApiResult, api::Error> {
-        let params = self.syspass.get_params(args, needs_password);
+        let params = self.syspass.get_params(args, needs_password)?;
         let req = JsonReq {
             jsonrpc: String::from("2.0"),
             method: method.to_owned(),
             params,
-            id: self.syspass.request_number.get(),
+            id: self.syspass.next_request_id(),
         };
         let response = self
             .syspass
             .send_request::<ApiResponse>(&self.syspass.config.host, &req);
 
-        self.syspass.request_number.set(self.syspass.request_number.get() + 1);
-
-        let ApiResponse { result, error } = response?;
+        let result = response.and_then(|ApiResponse { result, error }| match (result, error) {
+            (Some(result), _) => Ok(result),
+            (None, Some(error)) => Err(api::Error::from_server_message(error.message)),
+            (None, None) => Err(api::Error::MalformedResponse(
+                "Response contains neither result nor error".to_owned(),
+            )),
+        });
+        if needs_password {
+            self.syspass.verify_password(result.as_ref().err());
+        }
 
-        result.map_or_else(|| Err(api::Error(error.expect("Invalid response").message)), Ok)
+        result
     }
 
     fn create_or_edit(id: Option<&u32>) -> &str {
@@ -72,6 +82,13 @@ impl Syspass {
         }
     }
 
+    // The server answers with the written files, {"app": .., "db": ..} for backups and {"xml": ..} for exports
+    fn run_config_task(&self, method: &str, args: Vec<(&str, String)>) -> Result<BTreeMap<String, String>, api::Error> {
+        let result = self.forge_and_send(method, Some(args), true)?;
+
+        parse(result.result.get("files").cloned().unwrap_or_default(), "files")
+    }
+
     fn save<T: Entity + DeserializeOwned>(
         &self,
         path: &str,
@@ -80,17 +97,21 @@ impl Syspass {
     ) -> Result<T, api::Error> {
         let create_or_edit = Self::create_or_edit(id);
         let method = path.to_owned() + "/" + create_or_edit;
-        if create_or_edit == Self::EDIT {
+        if let Some(id) = id.filter(|_| create_or_edit == Self::EDIT) {
             args = args.map(|mut args| {
-                args.push(("id", id.expect("Already checked with create or edit").to_string()));
+                args.push(("id", id.to_string()));
                 args
             });
         }
 
         match self.forge_and_send(&method, args, true) {
             Ok(result) => {
-                let mut entity = serde_json::from_value::<T>(result.result).expect("Failed to convert to entity");
-                entity.set_id(result.item_id.expect("Id should be set"));
+                let mut entity = parse::<T>(result.result, path)?;
+                entity.set_id(
+                    result
+                        .item_id
+                        .ok_or_else(|| api::Error::MalformedResponse(format!("{path}: itemId is missing")))?,
+                );
 
                 Ok(entity)
             }
@@ -115,9 +136,9 @@ impl api::Client for Syspass {
     fn search_account(&self, search: Vec<(&str, String)>, usage: bool) -> Result<Vec<Account>, api::Error> {
         match self.forge_and_send("account/search", Some(search), false) {
             Ok(result) => {
-                let mut list: Vec<Account> = serde_json::from_value(result.result).expect("Invalid response");
+                let mut list: Vec<Account> = parse(result.result, "account list")?;
                 let usage_data: HashMap<u32, u32> = if usage {
-                    Config::get_usage_data(None)
+                    Config::get_usage_data(self.syspass.config.profile.as_deref(), None)
                 } else {
                     HashMap::from([(0, 0)])
                 };
@@ -133,7 +154,7 @@ impl api::Client for Syspass {
     fn get_password(&self, account: &Account) -> Result<ViewPassword, api::Error> {
         match self.forge_and_send(
             "account/viewPass",
-            Some(vec![("id", account.id().expect("Should not be empty").to_string())]),
+            Some(vec![("id", get_account_id(account)?.to_string())]),
             true,
         ) {
             Ok(result) => Ok(ViewPassword {
@@ -141,10 +162,9 @@ impl api::Client for Syspass {
                 password: result
                     .result
                     .get("password")
-                    .expect("Failed to get password")
-                    .as_str()
-                    .expect("Failed to get password option")
-                    .to_string(),
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| api::Error::MalformedResponse("password is missing".to_owned()))?
+                    .to_owned(),
             }),
             Err(error) => Err(error),
         }
@@ -153,8 +173,7 @@ impl api::Client for Syspass {
     fn get_clients(&self) -> Result<Vec<Client>, api::Error> {
         match self.forge_and_send("client/search", None, false) {
             Ok(result) => {
-                let mut list: Vec<Client> =
-                    serde_json::from_value(result.result).expect("Failed to convert client list");
+                let mut list: Vec<Client> = parse(result.result, "client list")?;
                 list.sort_by(|a, b| a.id().cmp(&b.id()));
                 Ok(list)
             }
@@ -165,8 +184,7 @@ impl api::Client for Syspass {
     fn get_categories(&self) -> Result<Vec<Category>, api::Error> {
         match self.forge_and_send("category/search", None, false) {
             Ok(result) => {
-                let mut list: Vec<Category> =
-                    serde_json::from_value(result.result).expect("Failed to convert category list");
+                let mut list: Vec<Category> = parse(result.result, "category list")?;
                 list.sort_by(|a, b| a.id().cmp(&b.id()));
                 Ok(list)
             }
@@ -180,7 +198,7 @@ impl api::Client for Syspass {
             client.id(),
             Some(vec![
                 ("name", client.name().to_owned()),
-                ("description", client.description().expect("").to_owned()),
+                ("description", client.description().unwrap_or_default().to_owned()),
                 ("global", client.is_global().clone().to_string()),
             ]),
         )
@@ -192,7 +210,7 @@ impl api::Client for Syspass {
             category.id(),
             Some(vec![
                 ("name", category.name().to_owned()),
-                ("description", category.description().expect("").to_owned()),
+                ("description", category.description().unwrap_or_default().to_owned()),
             ]),
         )
     }
@@ -205,7 +223,7 @@ impl api::Client for Syspass {
                 ("name", account.name().to_owned()),
                 ("categoryId", account.category_id().to_string()),
                 ("clientId", account.client_id().to_string()),
-                ("pass", account.pass().expect("Password is required").to_owned()),
+                ("pass", get_account_pass(account)?.to_owned()),
                 ("login", account.login().to_owned()),
                 ("url", account.url().unwrap_or_default().to_owned()),
                 ("notes", account.notes().unwrap_or_default().to_owned()),
@@ -223,11 +241,34 @@ impl api::Client for Syspass {
             ]),
             true,
         ) {
-            Ok(result) => Ok(serde_json::from_value::<Account>(result.result).expect("Failed convert account")),
+            Ok(result) => Ok(parse::<Account>(result.result, "account")?),
             Err(error) => Err(error),
         }
     }
 
+    fn can_change_password(&self) -> bool {
+        true
+    }
+
+    fn edit_account(&self, account: &EditAccount) -> Result<Account, api::Error> {
+        let mut args = vec![
+            ("name", account.name.clone()),
+            ("categoryId", account.category_id.to_string()),
+            ("clientId", account.client_id.to_string()),
+            ("login", account.login.clone()),
+            ("url", account.url.clone()),
+            ("notes", account.notes.clone()),
+            ("expireDate", account.expire_date.to_string()),
+        ];
+        // Sent as an integer array, and left out when empty so sysPass keeps the current tags
+        if !account.tags_id.is_empty() {
+            let tags = account.tags_id.iter().map(ToString::to_string).collect::<Vec<_>>();
+            args.push(("tagsId", tags.join(",")));
+        }
+
+        self.save::<Account>("account", Some(&account.id), Some(args))
+    }
+
     fn delete_client(&self, id: u32) -> Result<bool, api::Error> {
         self.delete_request("client/delete", id)
     }
@@ -242,25 +283,74 @@ impl api::Client for Syspass {
 
     fn view_account(&self, id: u32) -> Result<Account, api::Error> {
         match self.forge_and_send("account/view", Some(vec![("id", id.to_string())]), true) {
-            Ok(result) => Ok(serde_json::from_value(result.result).expect("Failed to convert account")),
+            Ok(result) => Ok(parse(result.result, "account")?),
             Err(error) => Err(error),
         }
     }
 
     fn get_category(&self, id: u32) -> Result<Category, api::Error> {
         match self.forge_and_send("category/view", Some(vec![("id", id.to_string())]), true) {
-            Ok(result) => Ok(serde_json::from_value(result.result).expect("Failed to convert category")),
+            Ok(result) => Ok(parse(result.result, "category")?),
             Err(error) => Err(error),
         }
     }
 
     fn get_client(&self, id: u32) -> Result<Client, api::Error> {
         match self.forge_and_send("client/view", Some(vec![("id", id.to_string())]), true) {
-            Ok(result) => Ok(serde_json::from_value(result.result).expect("Failed to convert client")),
+            Ok(result) => Ok(parse(result.result, "client")?),
             Err(error) => Err(error),
         }
     }
 
+    fn get_tags(&self) -> Result<Vec<Tag>, api::Error> {
+        match self.forge_and_send("tag/search", None, false) {
+            Ok(result) => {
+                let mut list: Vec<Tag> = parse(result.result, "tag list")?;
+                list.sort_by(|a, b| a.id().cmp(&b.id()));
+                Ok(list)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn get_tag(&self, id: u32) -> Result<Tag, api::Error> {
+        match self.forge_and_send("tag/view", Some(vec![("id", id.to_string())]), true) {
+            Ok(result) => Ok(parse(result.result, "tag")?),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn save_tag(&self, tag: &Tag) -> Result<Tag, api::Error> {
+        self.save::<Tag>("tag", tag.id(), Some(vec![("name", tag.name().to_owned())]))
+    }
+
+    fn delete_tag(&self, id: u32) -> Result<bool, api::Error> {
+        self.delete_request("tag/delete", id)
+    }
+
+    fn run_backup(&self, path: Option<&str>) -> Result<BTreeMap<String, String>, api::Error> {
+        let args = path.map(|path| ("path", path.to_owned())).into_iter().collect();
+
+        self.run_config_task("config/backup", args)
+    }
+
+    fn run_export(&self, password: Option<&str>, path: Option<&str>) -> Result<BTreeMap<String, String>, api::Error> {
+        // Only given values are sent, sysPass uses its own defaults for the others
+        let args = [("password", password), ("path", path)]
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value.to_owned())))
+            .collect();
+
+        self.run_config_task("config/export", args)
+    }
+
+    fn get_expiring_accounts(&self) -> Result<Vec<Account>, api::Error> {
+        let mut accounts = self.search_account(vec![("count", api::ACCOUNT_LIMIT.to_owned())], false)?;
+        accounts.retain(|account| account.expire_date().is_some());
+
+        Ok(accounts)
+    }
+
     fn get_config(&self) -> &Config {
         &self.syspass.config
     }
@@ -268,12 +358,16 @@ impl api::Client for Syspass {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
     use std::path::Path;
 
     use mockito::{Mock, ServerGuard};
+    use serde_json::Value;
+    use tempfile::tempdir;
     use test_case::test_case;
 
-    use crate::api::account::{Account, ChangePassword};
+    use crate::api;
+    use crate::api::account::{Account, ChangePassword, EditAccount};
     use crate::api::entity::Entity;
     use crate::api::syspass::v3::Syspass;
     use crate::api::Client;
@@ -304,7 +398,11 @@ mod tests {
         let response = test.1.search_account(vec![], false);
         assert!(response.is_err());
         let search = format!("Server responded with code {status}");
-        assert!(response.err().expect("Err was not set").0.contains(search.as_str()));
+        assert!(response
+            .err()
+            .expect("Err was not set")
+            .to_string()
+            .contains(search.as_str()));
 
         test.0.assert();
     }
@@ -320,7 +418,11 @@ mod tests {
 
         assert!(accounts.is_err());
         let search = format!("Server responded with code {status}");
-        assert!(accounts.err().expect("Err should be set").0.contains(search.as_str()));
+        assert!(accounts
+            .err()
+            .expect("Err should be set")
+            .to_string()
+            .contains(search.as_str()));
 
         test.0.assert();
     }
@@ -364,6 +466,7 @@ mod tests {
             password_timeout: None,
             no_clipboard: false,
             no_shell: false,
+            ..Default::default()
         });
 
         assert!(client.search_account(vec![], false).is_err());
@@ -386,6 +489,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_edit_account() {
+        let test = create_server_response(Some("tests/responses/syspass/v3/account_edit.json"), 200);
+        let edit = EditAccount {
+            id: 1,
+            name: "renamed account".to_owned(),
+            category_id: 1,
+            client_id: 1,
+            login: "new login".to_owned(),
+            url: String::new(),
+            notes: String::new(),
+            tags_id: vec![1],
+            expire_date: 0,
+        };
+
+        let account = test.1.edit_account(&edit).expect("Response should not have failed");
+
+        assert_eq!("renamed account", account.name());
+        assert_eq!("new login", account.login());
+        assert_eq!(&1, account.id().expect("Id should be set"));
+
+        test.0.assert();
+    }
+
+    // An empty tagsId is left out of account/edit so sysPass keeps the account's tags
+    #[test_case(vec![], None; "empty")]
+    #[test_case(vec![4, 7], Some("[4,7]"); "given")]
+    fn test_edit_account_tags(tags_id: Vec<u32>, expected: Option<&str>) {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let mut test = create_server_response(Some("tests/responses/syspass/v3/account_edit.json"), 200);
+        test.1.syspass.config.record = Some(dir.path().to_path_buf());
+        let edit = EditAccount {
+            id: 1,
+            name: "renamed account".to_owned(),
+            category_id: 1,
+            client_id: 1,
+            login: "new login".to_owned(),
+            url: String::new(),
+            notes: String::new(),
+            tags_id,
+            expire_date: 0,
+        };
+
+        test.1.edit_account(&edit).expect("Response should not have failed");
+
+        let request: Value = serde_json::from_str(
+            &fs::read_to_string(dir.path().join("001-account_edit.request.json")).expect("Request was not recorded"),
+        )
+        .expect("Request should be JSON");
+        assert_eq!(
+            expected,
+            request["params"].get("tagsId").map(Value::to_string).as_deref()
+        );
+        test.0.assert();
+    }
+
     #[test]
     fn test_get_password() {
         let test = create_server_response(Some("tests/responses/syspass/v3/account_view_password.json"), 200);
@@ -427,7 +586,8 @@ mod tests {
                 panic!("Request should have failed")
             }
             Err(e) => {
-                assert_eq!("The account doesn't exist", e.0);
+                assert!(matches!(e, api::Error::NotFound(_)));
+                assert_eq!("The account doesn't exist", e.to_string());
             }
         }
     }
@@ -472,6 +632,75 @@ mod tests {
         test.0.assert();
     }
 
+    #[test]
+    fn test_get_tags() {
+        let test = create_server_response(Some("tests/responses/syspass/v3/tag_list.json"), 200);
+
+        let tags = test.1.get_tags();
+
+        tags.map_or_else(
+            |_| panic!("Tags should not have failed"),
+            |tags| {
+                assert_eq!(3, tags.len());
+                assert_eq!("database", tags[0].name());
+                assert_eq!(&3, tags[2].id().expect("Id should be set"));
+            },
+        );
+
+        test.0.assert();
+    }
+
+    #[test]
+    fn test_remove_tag() {
+        let test = create_server_response(Some("tests/responses/syspass/v3/tag_delete.json"), 200);
+        let response = test.1.delete_tag(3);
+
+        response.map_or_else(
+            |_| panic!("Request should not have failed"),
+            |response| {
+                assert!(response);
+            },
+        );
+    }
+
+    #[test]
+    fn test_run_backup() {
+        let test = create_server_response(Some("tests/responses/syspass/v3/config_backup.json"), 200);
+        let files = test.1.run_backup(None).expect("Backup should not have failed");
+
+        assert_eq!(
+            Some("/var/www/html/sysPass/app/backup/sysPass_db-f0a1b2c3.sql.gz"),
+            files.get("db").map(String::as_str)
+        );
+        assert_eq!(2, files.len());
+        test.0.assert();
+    }
+
+    #[test]
+    fn test_run_export() {
+        let test = create_server_response(Some("tests/responses/syspass/v3/config_export.json"), 200);
+        let files = test
+            .1
+            .run_export(Some("secret"), None)
+            .expect("Export should not have failed");
+
+        assert_eq!(
+            Some("/var/www/html/sysPass/app/backup/sysPass_export-f0a1b2c3.xml"),
+            files.get("xml").map(String::as_str)
+        );
+        test.0.assert();
+    }
+
+    #[test]
+    fn test_get_expiring_accounts() {
+        let test = create_server_response(Some("tests/responses/syspass/v3/account_search_expiring.json"), 200);
+        let accounts = test.1.get_expiring_accounts().expect("Search should not have failed");
+
+        assert_eq!(vec![Some(&46)], accounts.iter().map(Entity::id).collect::<Vec<_>>());
+        assert_eq!(Some(1_695_678_542), accounts[0].expire_date());
+        test.0.assert();
+    }
+
     #[test]
     fn test_view_account() {
         let test = create_server_response(Some("tests/responses/syspass/v3/view_account.json"), 200);
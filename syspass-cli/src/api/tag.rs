@@ -0,0 +1,99 @@
+use std::fmt::{Display, Formatter};
+
+use colored::Colorize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::FuzzySelect;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::api;
+use crate::api::entity::Entity;
+use crate::prompt::ask_prompt;
+
+const ID_EMPTY: &str = "Id should not be empty";
+
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    id: Option<u32>,
+    name: String,
+}
+
+impl Tag {
+    pub const fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        name.clone_into(&mut self.name);
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}. {}", self.id().unwrap_or(&0_u32), self.name())
+    }
+}
+
+impl Entity for Tag {
+    fn id(&self) -> Option<&u32> {
+        self.id.as_ref()
+    }
+
+    fn set_id(&mut self, id: u32) {
+        self.id = Some(id);
+    }
+}
+
+pub fn ask_for(api_client: &dyn api::Client) -> Result<u32, api::Error> {
+    let tags = api_client.get_tags()?;
+
+    FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select the right tag (ESC for new):")
+        .max_length(10)
+        .items(&tags)
+        .interact_opt()
+        .map_err(|_| api::Error::Aborted)?
+        .map_or_else(
+            || loop {
+                let new_tag = Tag {
+                    id: None,
+                    name: ask_prompt("Tag name", true, "")?,
+                };
+
+                match api_client.save_tag(&new_tag) {
+                    Ok(tag) => break Ok(tag.id.expect(ID_EMPTY)),
+                    Err(error) => {
+                        error!("{} Failed to save tag: {}", "\u{2716}".bright_red(), error);
+                    }
+                }
+            },
+            |choice| Ok(*tags[choice].id().expect(ID_EMPTY)),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::tag::Tag;
+
+    #[test]
+    fn test_display_tag() {
+        assert_eq!(
+            "1. name",
+            Tag {
+                id: Some(1),
+                name: "name".to_string(),
+            }
+            .to_string()
+        );
+
+        assert_eq!(
+            "0. name",
+            Tag {
+                id: None,
+                name: "name".to_string(),
+            }
+            .to_string()
+        );
+    }
+}
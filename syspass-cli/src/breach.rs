@@ -0,0 +1,291 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+use clap::{arg, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::{error, warn};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+
+use crate::api::entity::Entity;
+use crate::api::{self, Client};
+use crate::list::print_table;
+use crate::output::Format;
+use crate::prompt::ask_for_password;
+use crate::CommandError;
+
+pub const COMMAND_NAME: &str = "check-breach";
+
+const HASH_LENGTH: usize = 40;
+
+// A Have I Been Pwned "SHA-1 ordered by hash" file with one HASH:COUNT line per password
+pub struct BreachFile {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl BreachFile {
+    // The first line is checked so a wrong or empty file is reported here and not as a breached password later
+    pub fn open(path: &str) -> Result<Self, String> {
+        let path = shellexpand::tilde(path).to_string();
+        let file = File::open(&path).map_err(|error| format!("Could not open breach file {path}: {error}"))?;
+        let len = file
+            .metadata()
+            .map_err(|error| format!("Could not read breach file {path}: {error}"))?
+            .len();
+
+        let mut breach_file = Self {
+            reader: BufReader::new(file),
+            len,
+        };
+        let (_, line) = breach_file
+            .line_from(0)
+            .map_err(|error| format!("Could not read breach file {path}: {error}"))?;
+        let hash = line.trim_end().split(':').next().unwrap_or_default();
+        if hash.len() != HASH_LENGTH || !hash.chars().all(|char| char.is_ascii_hexdigit()) {
+            return Err(format!("{path} is not a SHA-1 ordered by hash breach file"));
+        }
+
+        Ok(breach_file)
+    }
+
+    // Binary search over the lines starting in [low, high), nothing but a few lines is ever read
+    pub fn count(&mut self, password: &str) -> Result<u64, String> {
+        let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+        let (mut low, mut high) = (0, self.len);
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (start, line) = self
+                .line_from(middle)
+                .map_err(|error| format!("Could not read breach file: {error}"))?;
+            if start >= high || line.is_empty() {
+                high = middle;
+                continue;
+            }
+
+            let (line_hash, count) = line
+                .trim_end()
+                .split_once(':')
+                .unwrap_or_else(|| (line.trim_end(), "1"));
+            if line_hash.len() != HASH_LENGTH {
+                return Err(format!("Invalid line in breach file: {}", line.trim_end()));
+            }
+            match line_hash.to_ascii_uppercase().cmp(&hash) {
+                std::cmp::Ordering::Equal => {
+                    return count
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid count in breach file: {}", line.trim_end()));
+                }
+                std::cmp::Ordering::Less => low = start + line.len() as u64,
+                std::cmp::Ordering::Greater => high = start,
+            }
+        }
+
+        Ok(0)
+    }
+
+    // The first whole line starting at or after position
+    fn line_from(&mut self, position: u64) -> std::io::Result<(u64, String)> {
+        let mut start = position;
+        let mut line = String::new();
+        if position > 0 {
+            self.reader.seek(SeekFrom::Start(position - 1))?;
+            start = position - 1 + self.reader.read_line(&mut line)? as u64;
+            line.clear();
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+        self.reader.read_line(&mut line)?;
+
+        Ok((start, line))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Breached {
+    id: u32,
+    name: String,
+    login: String,
+    count: u64,
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Check passwords against a downloaded Have I Been Pwned hash file")
+        .arg(
+            arg!(--file <FILE> "SHA-1 ordered by hash file, defaults to breachFile")
+                .required(false)
+                .value_hint(ValueHint::FilePath),
+        )
+        .arg(arg!(--accounts "Check the passwords of all accounts instead of a typed one"))
+        .arg(
+            arg!(-a --category <CATEGORYID> "Only check the accounts of the category")
+                .required(false)
+                .requires("accounts")
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
+    let config = api_client.get_config();
+    let path = matches
+        .get_one::<String>("file")
+        .or(config.breach_file.as_ref())
+        .ok_or("No breach file configured, set breachFile or use --file")?;
+    let mut breach_file = BreachFile::open(path)?;
+
+    if matches.get_flag("accounts") {
+        if config.host.is_empty() && config.replay.is_none() {
+            Err(CommandError::MissingHost)?;
+        }
+        return check_accounts(matches, api_client, &mut breach_file);
+    }
+
+    if quiet {
+        Err("Could not ask for password")?;
+    }
+    let count = breach_file.count(&ask_for_password("Password: ", false)?)?;
+    let format = Format::from(matches);
+    if format.is_structured() {
+        format.print_one(&serde_json::json!({ "count": count }))?;
+    } else if count == 0 {
+        warn!("{} Password not found in known breaches", "\u{2714}".bright_green());
+    } else {
+        warn!(
+            "{} Password appears {count} times in known breaches",
+            "\u{2716}".bright_red()
+        );
+    }
+
+    Ok(u8::from(count > 0))
+}
+
+fn check_accounts(
+    matches: &ArgMatches,
+    api_client: &dyn Client,
+    breach_file: &mut BreachFile,
+) -> Result<u8, Box<dyn Error>> {
+    let mut search = vec![("count", api::ACCOUNT_LIMIT.to_owned())];
+    if let Some(category) = matches.get_one::<u32>("category") {
+        search.push(("categoryId", category.to_string()));
+    }
+    let accounts = api_client.search_account(search, false)?;
+
+    let mut failed = false;
+    let mut breached = Vec::new();
+    for account in &accounts {
+        let count = match api_client.get_password(account) {
+            Ok(password) => breach_file.count(&password.password)?,
+            Err(api_error) => {
+                error!("Could not read the password of {}: {api_error}", account.name());
+                failed = true;
+                continue;
+            }
+        };
+        if count > 0 {
+            breached.push(Breached {
+                id: account.id().copied().unwrap_or_default(),
+                name: account.name().to_owned(),
+                login: account.login().to_owned(),
+                count,
+            });
+        }
+    }
+
+    let format = Format::from(matches);
+    if format.is_structured() {
+        format.print_list(&breached)?;
+    } else if breached.is_empty() {
+        warn!(
+            "{} None of {} passwords found in known breaches",
+            "\u{2714}".bright_green(),
+            accounts.len()
+        );
+    } else {
+        print_table(
+            &["Id", "Name", "Login", "Seen"],
+            breached
+                .iter()
+                .map(|account| {
+                    vec![
+                        account.id.to_string(),
+                        account.name.clone(),
+                        account.login.clone(),
+                        account.count.to_string().bright_red().to_string(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    Ok(u8::from(failed || !breached.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use sha1::{Digest, Sha1};
+    use test_case::test_case;
+
+    use crate::breach::BreachFile;
+
+    fn write_file(passwords: &[(&str, u64)], newline: &str) -> tempfile::NamedTempFile {
+        let mut lines: Vec<String> = passwords
+            .iter()
+            .map(|(password, count)| format!("{:X}:{count}", Sha1::digest(password.as_bytes())))
+            .chain((0..500).map(|filler| format!("{:X}:1", Sha1::digest(format!("filler {filler}").as_bytes()))))
+            .collect();
+        lines.sort();
+
+        let mut file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        for line in lines {
+            write!(file, "{line}{newline}").expect("Failed to write breach file");
+        }
+        file
+    }
+
+    #[test_case("\n"; "unix")]
+    #[test_case("\r\n"; "windows")]
+    fn test_count(newline: &str) {
+        let passwords = [("password", 9_659_365), ("123456", 37_359_195), ("hunter2", 17_043)];
+        let file = write_file(&passwords, newline);
+        let mut breach_file = BreachFile::open(&file.path().to_string_lossy()).expect("Breach file should open");
+
+        for (password, count) in passwords {
+            assert_eq!(Ok(count), breach_file.count(password));
+        }
+        for filler in [0, 1, 250, 499] {
+            assert_eq!(Ok(1), breach_file.count(&format!("filler {filler}")));
+        }
+        assert_eq!(Ok(0), breach_file.count("Dg7#kL2!pQ9zWm4x"));
+        assert_eq!(Ok(0), breach_file.count(""));
+    }
+
+    #[test]
+    fn test_count_single_line() {
+        let file = write_file(&[], "\n");
+        std::fs::write(file.path(), "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:3\n").expect("Failed to write");
+        let mut breach_file = BreachFile::open(&file.path().to_string_lossy()).expect("Breach file should open");
+
+        assert_eq!(Ok(3), breach_file.count("password"));
+        assert_eq!(Ok(0), breach_file.count("other"));
+    }
+
+    #[test_case(""; "empty")]
+    #[test_case("not a breach file\n"; "text")]
+    #[test_case("5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD:3\n"; "short hash")]
+    fn test_open_invalid(content: &str) {
+        let file = write_file(&[], "\n");
+        std::fs::write(file.path(), content).expect("Failed to write");
+
+        assert!(BreachFile::open(&file.path().to_string_lossy())
+            .is_err_and(|error| error.contains("is not a SHA-1 ordered by hash breach file")));
+        assert!(BreachFile::open("/nonexistent/breach.txt").is_err());
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use chrono::DateTime;
+use clap::{ArgMatches, Command};
+use colored::Colorize;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api::account::Account;
+use crate::api::category::Category;
+use crate::api::client::Client as SyspassClient;
+use crate::api::entity::Entity;
+use crate::api::{self, Client};
+use crate::config::{self, Config};
+use crate::CommandError;
+
+pub const COMMAND_NAME: &str = "cache";
+
+const REFRESH: &str = "refresh";
+const STATUS: &str = "status";
+const CLEAR: &str = "clear";
+
+const MAGIC: &[u8] = b"SPCACHE1";
+const NONCE_SIZE: usize = 24;
+const KEY_SIZE: usize = 32;
+const DEFAULT_TTL: u64 = 3600;
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Manage the local account cache used by search")
+        .long_about(
+            "Manage the local account cache used by search.\n\n\
+             The cache is encrypted with a random key stored next to it in ~/.syspass/cache.key. \
+             Anyone who can read that directory can read the cached account names, logins and urls. \
+             Passwords are never cached.",
+        )
+        .subcommand_required(true)
+        .subcommand(Command::new(REFRESH).about("Download account, category and client names"))
+        .subcommand(Command::new(STATUS).about("Show when the cache was refreshed"))
+        .subcommand(Command::new(CLEAR).about("Delete the cache"))
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client) -> Result<u8, Box<dyn Error>> {
+    let (name, _) = matches.subcommand().ok_or(CommandError::NotFound)?;
+    let config = api_client.get_config();
+
+    match name {
+        REFRESH => {
+            let cache = Cache::fetch(api_client)?;
+            let path = cache.save(config, None)?;
+            warn!(
+                "{} Cached {} account(s) in {}",
+                "\u{2714}".bright_green(),
+                cache.accounts.len(),
+                path.display()
+            );
+
+            Ok(0)
+        }
+        STATUS => status(config),
+        CLEAR => {
+            let path = config::get_cache_path(config.profile.as_deref(), None);
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+            warn!("{} Cache cleared", "\u{2714}".bright_green());
+
+            Ok(0)
+        }
+        _ => Err(CommandError::NotFound)?,
+    }
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn status(config: &Config) -> Result<u8, Box<dyn Error>> {
+    let Some(cache) = Cache::load(config, None) else {
+        warn!(
+            "{} No cache, run `syspass-cli cache refresh` to create it",
+            "\u{2716}".bright_red()
+        );
+        return Ok(1);
+    };
+
+    let updated = i64::try_from(cache.updated)
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map_or_else(String::new, |date| date.format("%Y-%m-%d %H:%M:%S UTC").to_string());
+    warn!(
+        "{} Cache updated {updated} with {} account(s), {} categories and {} client(s){}",
+        "\u{2714}".bright_green(),
+        cache.accounts.len(),
+        cache.categories.len(),
+        cache.clients.len(),
+        if cache.is_fresh(get_ttl(config)) {
+            ""
+        } else {
+            ", expired"
+        }
+    );
+
+    Ok(0)
+}
+
+pub fn get_ttl(config: &Config) -> u64 {
+    config.cache_ttl.unwrap_or(DEFAULT_TTL)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// The random key is created with the first cache and only readable by its owner.
+// Host and token are mixed in so a cache written for another server can't be opened
+fn get_cipher(config: &Config, dir: Option<&str>, create: bool) -> Result<XChaCha20Poly1305, api::Error> {
+    let path = config::get_cache_key_path(dir);
+    let secret = match fs::read(&path) {
+        Ok(secret) if secret.len() == KEY_SIZE => secret,
+        _ if create => {
+            let secret = XChaCha20Poly1305::generate_key(&mut OsRng);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|error| api::Error::Other(format!("Could not create {}: {error}", parent.display())))?;
+            }
+            config::write_private(&path, &secret)
+                .map_err(|error| api::Error::Other(format!("Could not write {}: {error}", path.display())))?;
+            secret.to_vec()
+        }
+        _ => return Err(api::Error::Other(format!("No cache key in {}", path.display()))),
+    };
+
+    let key = Sha256::new()
+        .chain_update(b"syspass-cli cache\0")
+        .chain_update(&secret)
+        .chain_update(config.host.as_bytes())
+        .chain_update([0])
+        .chain_update(config.token.as_bytes())
+        .finalize();
+
+    Ok(XChaCha20Poly1305::new(&key))
+}
+
+fn encrypt(config: &Config, dir: Option<&str>, data: &[u8]) -> Result<Vec<u8>, api::Error> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = get_cipher(config, dir, true)?
+        .encrypt(&nonce, Payload { msg: data, aad: MAGIC })
+        .map_err(|_| api::Error::Other("Could not encrypt the cache".to_owned()))?;
+
+    Ok([MAGIC, nonce.as_slice(), &ciphertext].concat())
+}
+
+fn decrypt(config: &Config, dir: Option<&str>, data: &[u8]) -> Option<Vec<u8>> {
+    let data = data.strip_prefix(MAGIC)?;
+    if data.len() < NONCE_SIZE {
+        return None;
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+
+    get_cipher(config, dir, false)
+        .ok()?
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: MAGIC,
+            },
+        )
+        .ok()
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Cache {
+    updated: u64,
+    accounts: Vec<Account>,
+    categories: Vec<Category>,
+    clients: Vec<SyspassClient>,
+}
+
+impl Cache {
+    fn fetch(api_client: &dyn Client) -> Result<Self, api::Error> {
+        Ok(Self {
+            updated: now(),
+            accounts: api_client.search_account(vec![("count", api::ACCOUNT_LIMIT.to_owned())], false)?,
+            categories: api_client.get_categories()?,
+            clients: api_client.get_clients()?,
+        })
+    }
+
+    // A cache written for another host or token, or without its key, can't be decrypted and counts as missing
+    fn load(config: &Config, dir: Option<&str>) -> Option<Self> {
+        let path = config::get_cache_path(config.profile.as_deref(), dir);
+        let data = fs::read(&path).ok()?;
+        let cache = decrypt(config, dir, &data).and_then(|data| serde_json::from_slice(&data).ok());
+        if cache.is_none() {
+            debug!("Ignoring unreadable cache {}", path.display());
+        }
+
+        cache
+    }
+
+    fn save(&self, config: &Config, dir: Option<&str>) -> Result<PathBuf, api::Error> {
+        let path = config::get_cache_path(config.profile.as_deref(), dir);
+        let data = serde_json::to_vec(self).map_err(|error| api::Error::Other(error.to_string()))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| api::Error::Other(format!("Could not create {}: {error}", parent.display())))?;
+        }
+        config::write_private(&path, &encrypt(config, dir, &data)?)
+            .map_err(|error| api::Error::Other(format!("Could not write {}: {error}", path.display())))?;
+
+        Ok(path)
+    }
+
+    fn is_fresh(&self, ttl: u64) -> bool {
+        now().saturating_sub(self.updated) < ttl
+    }
+
+    fn get_haystack(&self, account: &Account) -> String {
+        let category = self
+            .categories
+            .iter()
+            .find(|category| category.id() == Some(account.category_id()))
+            .map_or("", Category::name);
+        let client = self
+            .clients
+            .iter()
+            .find(|client| client.id() == Some(account.client_id()))
+            .map_or_else(|| account.client_name().unwrap_or_default(), SyspassClient::name);
+
+        [
+            account.name(),
+            account.login(),
+            account.url().unwrap_or_default(),
+            client,
+            category,
+        ]
+        .join(" ")
+    }
+
+    // Plain substring matches win, the fuzzy matches are only used when there are none.
+    // Like the server search, the most used accounts come first
+    fn search(&self, text: &str, category: u32, usage: &HashMap<u32, u32>) -> Vec<Account> {
+        let matcher = SkimMatcherV2::default();
+        let lowercase = text.to_lowercase();
+        let mut scored: Vec<(bool, i64, &Account)> = self
+            .accounts
+            .iter()
+            .filter(|account| category == 0 || *account.category_id() == category)
+            .filter_map(|account| {
+                let haystack = self.get_haystack(account);
+                matcher
+                    .fuzzy_match(&haystack, text)
+                    .map(|score| (haystack.to_lowercase().contains(&lowercase), score, account))
+            })
+            .collect();
+
+        if scored.iter().any(|(exact, _, _)| *exact) {
+            scored.retain(|(exact, _, _)| *exact);
+        }
+
+        let get_usage = |account: &Account| account.id().and_then(|id| usage.get(id)).copied().unwrap_or(0);
+        scored.sort_by(|(_, left_score, left), (_, right_score, right)| {
+            get_usage(right)
+                .cmp(&get_usage(left))
+                .then_with(|| right_score.cmp(left_score))
+                .then_with(|| left.id().cmp(&right.id()))
+        });
+
+        scored.into_iter().map(|(_, _, account)| account.clone()).collect()
+    }
+}
+
+// Returns None when the server has to be asked. An expired cache is refreshed for the next search.
+// A replay only answers from the recorded traffic, never from live data in the cache
+pub fn search(matches: &ArgMatches, config: &Config, text: &str, category: u32, usage: bool) -> Option<Vec<Account>> {
+    let ttl = get_ttl(config);
+    if ttl == 0 || config.replay.is_some() || !config::get_cache_path(config.profile.as_deref(), None).exists() {
+        return None;
+    }
+
+    match Cache::load(config, None) {
+        Some(cache) if cache.is_fresh(ttl) => {
+            let usage_data = if usage {
+                Config::get_usage_data(config.profile.as_deref(), None)
+            } else {
+                HashMap::new()
+            };
+            let accounts = cache.search(text, category, &usage_data);
+            if accounts.is_empty() {
+                debug!("No match in the cache, asking the server");
+                return None;
+            }
+            info!("Found {} account(s) in the cache", accounts.len());
+
+            Some(accounts)
+        }
+        _ => {
+            refresh_in_background(matches);
+            None
+        }
+    }
+}
+
+// Called after the CLI changes accounts, categories or clients so the next search asks the server and refreshes
+pub fn invalidate(config: &Config) {
+    if config.replay.is_some() {
+        return;
+    }
+    let Some(mut cache) = Cache::load(config, None) else {
+        return;
+    };
+
+    cache.updated = 0;
+    match cache.save(config, None) {
+        Ok(_) => debug!("Cache marked as expired"),
+        Err(error) => debug!("Could not expire the cache: {error}"),
+    }
+}
+
+fn refresh_in_background(matches: &ArgMatches) {
+    let mut args: Vec<String> = vec!["-q".to_owned()];
+    if let Some(file) = config::get_config_file(matches) {
+        args.extend(["--config".to_owned(), file.to_string_lossy().to_string()]);
+    }
+    if let Some(profile) = config::get_profile(matches) {
+        args.extend(["--profile".to_owned(), profile.to_owned()]);
+    }
+    for flag in matches
+        .try_get_many::<String>(config::SET)
+        .ok()
+        .flatten()
+        .into_iter()
+        .flatten()
+    {
+        args.extend(["--set".to_owned(), flag.clone()]);
+    }
+
+    let child = env::current_exe().and_then(|path| {
+        process::Command::new(path)
+            .args(args)
+            .args([COMMAND_NAME, REFRESH])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+
+    match child {
+        Ok(_) => debug!("Refreshing the cache in the background"),
+        Err(error) => debug!("Could not refresh the cache: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tempfile::tempdir;
+
+    use crate::api::account::Account;
+    use crate::api::category::Category;
+    use crate::api::client::Client;
+    use crate::api::entity::Entity;
+    use crate::cache::{now, Cache};
+    use crate::config::{self, Config};
+
+    fn account(id: u32, name: &str, login: &str, url: Option<&str>, category_id: u32) -> Account {
+        Account::new(
+            Some(id),
+            name.to_owned(),
+            login.to_owned(),
+            url.map(ToOwned::to_owned),
+            None,
+            category_id,
+            1,
+            None,
+            None,
+        )
+    }
+
+    fn cache() -> Cache {
+        Cache {
+            updated: now(),
+            accounts: vec![
+                account(1, "Production DB", "admin", Some("db.example.org"), 1),
+                account(2, "Staging DB", "deploy", None, 1),
+                account(3, "Mail", "postmaster", Some("https://mail.example.org"), 2),
+            ],
+            categories: vec![
+                Category::new(Some(1), "Databases".to_owned(), None),
+                Category::new(Some(2), "Web".to_owned(), None),
+            ],
+            clients: vec![Client::new(Some(1), "Acme".to_owned(), None, 0)],
+        }
+    }
+
+    fn config(token: &str) -> Config {
+        Config {
+            host: "https://example.org/api.php".to_owned(),
+            token: token.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn ids(accounts: &[Account]) -> Vec<u32> {
+        accounts.iter().filter_map(|account| account.id().copied()).collect()
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let dir_str = dir.path().to_str();
+
+        let path = cache().save(&config("token"), dir_str).expect("Failed to save cache");
+        let data = std::fs::read(&path).expect("Cache was not written");
+        assert!(!String::from_utf8_lossy(&data).contains("Production"));
+        assert_eq!(config::get_cache_path(None, dir_str), path);
+
+        let loaded = Cache::load(&config("token"), dir_str).expect("Failed to load cache");
+        assert_eq!(vec![1, 2, 3], ids(&loaded.accounts));
+        assert_eq!(2, loaded.categories.len());
+
+        assert!(Cache::load(&config("other token"), dir_str).is_none());
+    }
+
+    #[test]
+    fn test_load_without_key() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let dir_str = dir.path().to_str();
+
+        cache().save(&config("token"), dir_str).expect("Failed to save cache");
+        let key_path = config::get_cache_key_path(dir_str);
+        assert_eq!(32, std::fs::read(&key_path).expect("Key was not written").len());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&key_path)
+                .expect("Key was not written")
+                .permissions()
+                .mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+
+        std::fs::remove_file(&key_path).expect("Failed to remove key");
+        assert!(Cache::load(&config("token"), dir_str).is_none());
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let mut cache = cache();
+        assert!(cache.is_fresh(60));
+
+        cache.updated -= 120;
+        assert!(!cache.is_fresh(60));
+        assert!(!cache.is_fresh(0));
+    }
+
+    #[test]
+    fn test_search() {
+        let cache = cache();
+        let usage = HashMap::new();
+
+        assert_eq!(vec![1, 2], ids(&cache.search("db", 0, &usage)));
+        assert_eq!(vec![2], ids(&cache.search("deploy", 0, &usage)));
+        assert_eq!(vec![3], ids(&cache.search("mail.example", 0, &usage)));
+        assert_eq!(vec![1, 2], ids(&cache.search("databases", 0, &usage)));
+        assert_eq!(vec![1, 2, 3], ids(&cache.search("acme", 0, &usage)));
+        assert_eq!(vec![3], ids(&cache.search("acme", 2, &usage)));
+        assert_eq!(vec![1], ids(&cache.search("prdctn", 0, &usage)));
+        assert!(cache.search("nothing", 0, &usage).is_empty());
+    }
+
+    #[test]
+    fn test_search_by_usage() {
+        let cache = cache();
+
+        assert_eq!(vec![2, 1], ids(&cache.search("db", 0, &HashMap::from([(2, 3)]))));
+    }
+}
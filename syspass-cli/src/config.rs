@@ -1,19 +1,85 @@
-use clap::ArgMatches;
-use colored::Colorize;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
 use std::ffi::OsString;
+use std::fmt;
 use std::fs;
 use std::io::ErrorKind::NotFound;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use clap::{ArgMatches, Command};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::generate::Policy;
+use crate::strength::Requirements;
+use crate::CommandError;
+
+pub mod init;
+pub mod show;
+
+pub const COMMAND_NAME: &str = "config";
 
 const CONFIG: &str = "config";
 const DEFAULT_CONFIG_DIR: &str = "/.syspass/";
+pub const PROFILE: &str = "profile";
+pub const SET: &str = "set";
+pub const RECORD: &str = "record";
+pub const REPLAY: &str = "replay";
+const DEFAULT_PROFILE: &str = "default";
 
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Text,
+    Bool,
+    Number,
+}
+
+pub const KEYS: [(&str, Kind); 16] = [
+    ("host", Kind::Text),
+    ("token", Kind::Text),
+    ("password", Kind::Text),
+    ("verifyHost", Kind::Bool),
+    ("apiVersion", Kind::Text),
+    ("passwordTimeout", Kind::Number),
+    ("noShell", Kind::Bool),
+    ("noClipboard", Kind::Bool),
+    ("passwordCommand", Kind::Text),
+    ("passwordFile", Kind::Text),
+    ("passwordFileIdentity", Kind::Text),
+    ("passwordSecretService", Kind::Bool),
+    ("cacheTtl", Kind::Number),
+    ("rotateHook", Kind::Text),
+    ("passwordPolicy", Kind::Text),
+    ("breachFile", Kind::Text),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueSource {
+    Default,
+    File,
+    Env(String),
+    Flag,
+}
+
+impl fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File => write!(f, "file"),
+            Self::Env(name) => write!(f, "env {name}"),
+            Self::Flag => write!(f, "--{SET}"),
+        }
+    }
+}
+
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Deserialize, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
+    #[serde(default)]
     pub host: String,
+    #[serde(default)]
     pub token: String,
     #[serde(default)]
     pub password: String,
@@ -26,6 +92,154 @@ pub struct Config {
     pub no_shell: bool,
     #[serde(default)]
     pub no_clipboard: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_file_identity: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub password_secret_service: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_hook: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_policy: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub password_policies: BTreeMap<String, Policy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_requirements: Option<Requirements>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breach_file: Option<String>,
+    #[serde(skip)]
+    pub profile: Option<String>,
+    #[serde(skip)]
+    pub sources: BTreeMap<String, ValueSource>,
+    #[serde(skip)]
+    pub record: Option<PathBuf>,
+    #[serde(skip)]
+    pub replay: Option<PathBuf>,
+}
+
+// The top level settings are the default profile unless a profile named "default" exists
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(flatten)]
+    config: Map<String, Value>,
+    #[serde(default)]
+    profiles: HashMap<String, Map<String, Value>>,
+}
+
+impl ConfigFile {
+    fn into_profile(mut self, name: Option<&str>) -> Result<Map<String, Value>, String> {
+        match name.filter(|name| *name != DEFAULT_PROFILE) {
+            None => Ok(self.profiles.remove(DEFAULT_PROFILE).unwrap_or(self.config)),
+            Some(name) => self
+                .profiles
+                .remove(name)
+                .ok_or_else(|| format!("Profile {name} not found in config file")),
+        }
+    }
+}
+
+fn get_env_name(key: &str) -> String {
+    key.chars().fold("SYSPASS_".to_owned(), |mut name, char| {
+        if char.is_ascii_uppercase() {
+            name.push('_');
+        }
+        name.push(char.to_ascii_uppercase());
+        name
+    })
+}
+
+fn parse_value(key: &str, kind: Kind, value: &str) -> Result<Value, String> {
+    match kind {
+        Kind::Text => Ok(Value::String(value.to_owned())),
+        Kind::Bool => match value.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(Value::Bool(true)),
+            "0" | "false" | "no" | "off" | "" => Ok(Value::Bool(false)),
+            _ => Err(format!("Invalid boolean for {key}: {value}")),
+        },
+        Kind::Number => value
+            .parse::<u64>()
+            .map(Value::from)
+            .map_err(|_| format!("Invalid number for {key}: {value}")),
+    }
+}
+
+// Layers are applied in order: defaults, config file, SYSPASS_* variables and --set flags
+fn load<F>(data: &str, profile: Option<&str>, env: F, flags: &[&str]) -> Result<Config, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let file: ConfigFile =
+        serde_json::from_str(data).map_err(|error| format!("JSON does not have correct format: {error}"))?;
+    let mut values = file.into_profile(profile)?;
+    let mut sources: BTreeMap<String, ValueSource> =
+        values.keys().map(|key| (key.clone(), ValueSource::File)).collect();
+
+    for (key, kind) in KEYS {
+        let name = get_env_name(key);
+        if let Some(value) = env(&name) {
+            values.insert(key.to_owned(), parse_value(key, kind, &value)?);
+            sources.insert(key.to_owned(), ValueSource::Env(name));
+        }
+    }
+
+    for flag in flags {
+        let (key, value) = flag
+            .split_once('=')
+            .ok_or_else(|| format!("Expected KEY=VALUE for --{SET}, got {flag}"))?;
+        let (key, kind) = KEYS
+            .iter()
+            .find(|(name, _)| *name == key)
+            .ok_or_else(|| format!("Unknown config key {key}"))?;
+        values.insert((*key).to_owned(), parse_value(key, *kind, value)?);
+        sources.insert((*key).to_owned(), ValueSource::Flag);
+    }
+
+    let mut config: Config = serde_json::from_value(Value::Object(values))
+        .map_err(|error| format!("JSON does not have correct format: {error}"))?;
+    config.profile = profile
+        .filter(|profile| *profile != DEFAULT_PROFILE)
+        .map(ToOwned::to_owned);
+    config.sources = sources;
+
+    Ok(config)
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Create or inspect the configuration")
+        .subcommand_required(true)
+        .subcommand(init::command_helper())
+        .subcommand(show::command_helper())
+}
+
+pub fn command(matches: &ArgMatches) -> Result<u8, Box<dyn Error>> {
+    match matches.subcommand() {
+        Some((init::COMMAND_NAME, matches)) => init::command(matches),
+        Some((show::COMMAND_NAME, matches)) => show::command(matches, &Config::try_from(matches)?),
+        _ => Err(CommandError::NotFound)?,
+    }
+}
+
+fn get_usage_file(profile: Option<&str>) -> String {
+    profile.map_or_else(|| "usage.json".to_owned(), |profile| format!("usage-{profile}.json"))
+}
+
+fn get_cache_file(profile: Option<&str>) -> String {
+    profile.map_or_else(|| "cache.bin".to_owned(), |profile| format!("cache-{profile}.bin"))
+}
+
+pub fn get_cache_path(profile: Option<&str>, dir: Option<&str>) -> PathBuf {
+    PathBuf::from(get_config_path(&get_cache_file(profile), dir))
+}
+
+// One key for every profile, kept outside the config so a copy of the config doesn't open the cache
+pub fn get_cache_key_path(dir: Option<&str>) -> PathBuf {
+    PathBuf::from(get_config_path("cache.key", dir))
 }
 
 fn get_config_path(file: &str, dir: Option<&str>) -> OsString {
@@ -62,32 +276,111 @@ where
     })
 }
 
-impl From<&ArgMatches> for Config {
-    fn from(value: &ArgMatches) -> Self {
-        let config_file = value
-            .get_one::<String>(CONFIG)
-            .map_or_else(|| "", |s| s.as_str())
-            .to_owned();
+pub fn get_config_file(matches: &ArgMatches) -> Option<PathBuf> {
+    matches
+        .try_get_one::<String>(CONFIG)
+        .ok()
+        .flatten()
+        .filter(|file| !file.is_empty())
+        .map(|file| PathBuf::from(shellexpand::tilde(file).to_string()))
+}
 
-        let data = if config_file.is_empty() {
-            get_config_file_or_write("config.json", None, Self::default())
-        } else {
-            fs::read_to_string(shellexpand::tilde(&config_file).to_string()).expect("Unable to read file")
+pub fn get_default_config_file() -> PathBuf {
+    PathBuf::from(get_config_path("config.json", None))
+}
+
+pub fn get_profile(matches: &ArgMatches) -> Option<&str> {
+    matches
+        .try_get_one::<String>(PROFILE)
+        .ok()
+        .flatten()
+        .map(String::as_str)
+        .filter(|profile| *profile != DEFAULT_PROFILE)
+}
+
+// Values of a single profile as written in the file, without any overrides
+pub fn read_profile(path: &Path, profile: Option<&str>) -> Map<String, Value> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str::<ConfigFile>(&data).ok())
+        .and_then(|file| file.into_profile(profile).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+pub fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(data)
+}
+
+#[cfg(not(unix))]
+pub fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    fs::write(path, data)
+}
+
+// A config file, profile, environment variable or --set flag that can't be used
+#[derive(Debug)]
+pub struct LoadError(pub String);
+
+impl LoadError {
+    pub const EXIT_CODE: u8 = 12;
+}
+
+impl Error for LoadError {}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<&ArgMatches> for Config {
+    type Error = LoadError;
+
+    fn try_from(value: &ArgMatches) -> Result<Self, Self::Error> {
+        let data = match get_config_file(value) {
+            Some(file) => fs::read_to_string(&file)
+                .map_err(|error| LoadError(format!("Couldn't read config file {}: {error}", file.display())))?,
+            None => match fs::read_to_string(get_default_config_file()) {
+                Ok(data) => data,
+                Err(error) if error.kind() == NotFound => "{}".to_owned(),
+                Err(error) => Err(LoadError(format!("Couldn't read config file: {error}")))?,
+            },
         };
 
-        serde_json::from_str(&data).expect("JSON does not have correct format.")
+        let flags: Vec<&str> = value
+            .try_get_many::<String>(SET)
+            .ok()
+            .flatten()
+            .map(|flags| flags.map(String::as_str).collect())
+            .unwrap_or_default();
+
+        let mut config = load(&data, get_profile(value), |name| std::env::var(name).ok(), &flags).map_err(LoadError)?;
+        config.record = value.try_get_one::<PathBuf>(RECORD).ok().flatten().cloned();
+        config.replay = value.try_get_one::<PathBuf>(REPLAY).ok().flatten().cloned();
+
+        Ok(config)
     }
 }
 
 impl Config {
-    pub fn get_usage_data(dir: Option<&str>) -> HashMap<u32, u32> {
-        let data = get_config_file_or_write("usage.json", dir, HashMap::from([(0, 0)]));
+    pub fn get_usage_data(profile: Option<&str>, dir: Option<&str>) -> HashMap<u32, u32> {
+        let data = get_config_file_or_write(&get_usage_file(profile), dir, HashMap::from([(0, 0)]));
 
         serde_json::from_str::<HashMap<u32, u32>>(&data).expect("JSON does not have correct format.")
     }
 
-    pub fn record_usage(id: u32, dir: Option<&str>) {
-        let mut usage = Self::get_usage_data(dir);
+    pub fn record_usage(id: u32, profile: Option<&str>, dir: Option<&str>) {
+        let mut usage = Self::get_usage_data(profile, dir);
 
         #[allow(clippy::option_if_let_else)]
         match usage.get_mut(&id) {
@@ -100,7 +393,7 @@ impl Config {
         };
 
         fs::write(
-            get_config_path("usage.json", dir),
+            get_config_path(&get_usage_file(profile), dir),
             serde_json::to_string::<HashMap<u32, u32>>(&usage).expect("Serialization failed") + "\n",
         )
         .expect("Unable to write file");
@@ -112,8 +405,9 @@ mod tests {
     use std::ffi::OsString;
 
     use tempfile::tempdir;
+    use test_case::test_case;
 
-    use crate::config::{get_config_file_or_write, get_config_path, Config};
+    use crate::config::{get_config_file_or_write, get_config_path, get_env_name, load, Config, ValueSource};
 
     fn create_temp_dir() -> OsString {
         let temp_path = tempdir().expect("Failed to create temp dir").path().to_owned();
@@ -123,7 +417,7 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
+    #[ignore = "Writes to the home directory"]
     fn test_get_config_file_or_write() {
         let temp = create_temp_dir();
         let temp_str = temp.to_str();
@@ -158,21 +452,136 @@ mod tests {
         let temp = create_temp_dir();
         let temp_str = temp.to_str();
 
-        let usage = Config::get_usage_data(temp_str);
+        let usage = Config::get_usage_data(None, temp_str);
         assert_eq!(usage.get(&31337), None);
 
-        Config::record_usage(31337, temp_str);
-        let usage = Config::get_usage_data(temp_str);
+        Config::record_usage(31337, None, temp_str);
+        let usage = Config::get_usage_data(None, temp_str);
         assert_eq!(usage.get(&31337), Some(&1));
 
-        Config::record_usage(31337, temp_str);
-        let usage = Config::get_usage_data(temp_str);
+        Config::record_usage(31337, None, temp_str);
+        let usage = Config::get_usage_data(None, temp_str);
         assert_eq!(usage.get(&31337), Some(&2));
 
-        let usage = Config::get_usage_data(temp_str);
+        let usage = Config::get_usage_data(None, temp_str);
         assert_eq!(usage.get(&31337), Some(&2));
         assert_eq!(usage.get(&31337), Some(&2));
 
         cleanup_temp_dir(temp_str);
     }
+
+    #[test]
+    fn test_record_usage_per_profile() {
+        let temp = create_temp_dir();
+        let temp_str = temp.to_str();
+
+        Config::record_usage(31337, Some("work"), temp_str);
+        assert_eq!(Config::get_usage_data(Some("work"), temp_str).get(&31337), Some(&1));
+        assert_eq!(Config::get_usage_data(None, temp_str).get(&31337), None);
+
+        cleanup_temp_dir(temp_str);
+    }
+
+    fn no_env(_name: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_single_profile_config() {
+        let config = load(
+            r#"{"host": "https://example.org", "token": "token"}"#,
+            None,
+            no_env,
+            &[],
+        )
+        .expect("Default profile should exist");
+
+        assert_eq!("https://example.org", config.host);
+        assert_eq!(None, config.profile);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let data = r#"{
+            "host": "https://top.example.org",
+            "token": "top",
+            "profiles": {
+                "work": {"host": "https://work.example.org", "token": "work", "apiVersion": "SyspassV2"}
+            }
+        }"#;
+
+        let work = load(data, Some("work"), no_env, &[]).expect("Work profile should exist");
+        assert_eq!("https://work.example.org", work.host);
+        assert_eq!(Some("SyspassV2".to_owned()), work.api_version);
+        assert_eq!(Some("work".to_owned()), work.profile);
+
+        assert_eq!("top", load(data, None, no_env, &[]).expect("Default profile").token);
+        assert_eq!(
+            "top",
+            load(data, Some("default"), no_env, &[]).expect("Default profile").token
+        );
+        assert!(load(data, Some("missing"), no_env, &[])
+            .is_err_and(|error| error == "Profile missing not found in config file"));
+    }
+
+    #[test]
+    fn test_default_profile_overrides_top_level() {
+        let config = load(
+            r#"{"host": "https://top.example.org", "profiles": {"default": {"host": "https://default.example.org"}}}"#,
+            None,
+            no_env,
+            &[],
+        )
+        .expect("Default profile should exist");
+
+        assert_eq!("https://default.example.org", config.host);
+    }
+
+    #[test_case("host", "SYSPASS_HOST")]
+    #[test_case("verifyHost", "SYSPASS_VERIFY_HOST")]
+    #[test_case("passwordFileIdentity", "SYSPASS_PASSWORD_FILE_IDENTITY")]
+    fn test_get_env_name(key: &str, expected: &str) {
+        assert_eq!(expected, get_env_name(key));
+    }
+
+    #[test]
+    fn test_layers() {
+        let env = |name: &str| match name {
+            "SYSPASS_TOKEN" => Some("env token".to_owned()),
+            "SYSPASS_VERIFY_HOST" => Some("true".to_owned()),
+            "SYSPASS_PASSWORD_TIMEOUT" => Some("30".to_owned()),
+            _ => None,
+        };
+
+        let config = load(
+            r#"{"host": "https://file.example.org", "token": "file token", "passwordTimeout": 10}"#,
+            None,
+            env,
+            &["passwordTimeout=45", "noShell=yes"],
+        )
+        .expect("Config should load");
+
+        assert_eq!("https://file.example.org", config.host);
+        assert_eq!("env token", config.token);
+        assert!(config.verify_host);
+        assert_eq!(Some(45), config.password_timeout);
+        assert!(config.no_shell);
+        assert!(!config.no_clipboard);
+
+        assert_eq!(Some(&ValueSource::File), config.sources.get("host"));
+        assert_eq!(
+            Some(&ValueSource::Env("SYSPASS_TOKEN".to_owned())),
+            config.sources.get("token")
+        );
+        assert_eq!(Some(&ValueSource::Flag), config.sources.get("passwordTimeout"));
+        assert_eq!(None, config.sources.get("noClipboard"));
+    }
+
+    #[test_case(&["verifyHost=maybe"], "Invalid boolean for verifyHost: maybe")]
+    #[test_case(&["passwordTimeout=soon"], "Invalid number for passwordTimeout: soon")]
+    #[test_case(&["unknown=1"], "Unknown config key unknown")]
+    #[test_case(&["host"], "Expected KEY=VALUE for --set, got host")]
+    fn test_invalid_flags(flags: &[&str], expected: &str) {
+        assert!(load("{}", None, no_env, flags).is_err_and(|error| error == expected));
+    }
 }
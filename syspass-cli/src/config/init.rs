@@ -0,0 +1,256 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{ArgMatches, Command};
+use colored::Colorize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Select};
+use log::{info, warn};
+use serde_json::{Map, Value};
+
+use crate::api::{self, Api};
+use crate::config::{
+    get_config_file, get_default_config_file, get_profile, read_profile, write_private, Config, DEFAULT_PROFILE,
+};
+use crate::prompt::ask_prompt;
+
+pub const COMMAND_NAME: &str = "init";
+
+const API_VERSIONS: [&str; 2] = ["SyspassV3", "SyspassV2"];
+const DETECT: &str = "Detect automatically";
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME).about("Create the config file interactively and test the connection")
+}
+
+pub fn command(matches: &ArgMatches) -> Result<u8, Box<dyn Error>> {
+    let path = get_config_file(matches).unwrap_or_else(get_default_config_file);
+    let profile = get_profile(matches);
+    let current = read_profile(&path, profile);
+    let current_text = |key: &str| current.get(key).and_then(Value::as_str).unwrap_or_default().to_owned();
+
+    let host = ask_prompt("Host (e.g. https://example.org/api.php): ", true, &current_text("host"))?;
+    let token = ask_prompt("API token: ", true, &current_text("token"))?;
+    let verify_host = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Verify TLS certificates?")
+        .default(current.get("verifyHost").and_then(Value::as_bool).unwrap_or(true))
+        .interact()?;
+
+    let versions = [DETECT, API_VERSIONS[0], API_VERSIONS[1]];
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("API version")
+        .items(&versions)
+        .default(0)
+        .interact()?;
+
+    let api_version = if selection == 0 {
+        detect_api_version(&host, &token, verify_host)?
+    } else {
+        test_connection(versions[selection], &host, &token, verify_host)
+            .map_err(|error| format!("Could not connect to {host}: {error}"))?;
+        versions[selection]
+    };
+
+    warn!("{} Connected to {host} using {api_version}", "\u{2714}".bright_green());
+
+    let values = Map::from_iter([
+        ("host".to_owned(), Value::from(host)),
+        ("token".to_owned(), Value::from(token)),
+        ("verifyHost".to_owned(), Value::from(verify_host)),
+        ("apiVersion".to_owned(), Value::from(api_version)),
+    ]);
+
+    if path.exists()
+        && !Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Update {}?", path.display()))
+            .default(true)
+            .interact()?
+    {
+        return Ok(1);
+    }
+
+    write_config(&path, profile, values)?;
+    warn!("{} Config saved to {}", "\u{2714}".bright_green(), path.display());
+
+    Ok(0)
+}
+
+fn test_connection(api_version: &str, host: &str, token: &str, verify_host: bool) -> Result<(), api::Error> {
+    let config = Config {
+        host: host.to_owned(),
+        token: token.to_owned(),
+        verify_host,
+        api_version: Some(api_version.to_owned()),
+        ..Default::default()
+    };
+
+    Api::from_str(api_version)
+        .expect("API version is known")
+        .get(config)
+        .get_categories()
+        .map(|_| ())
+}
+
+// v2 only knows getCategories and v3 only category/search, so the first call that succeeds wins
+fn detect_api_version(host: &str, token: &str, verify_host: bool) -> Result<&'static str, String> {
+    let mut errors = vec![];
+
+    for api_version in API_VERSIONS {
+        info!("Trying {api_version}");
+        match test_connection(api_version, host, token, verify_host) {
+            Ok(()) => return Ok(api_version),
+            Err(error) => errors.push(format!("{api_version}: {error}")),
+        }
+    }
+
+    Err(format!("Could not connect to {host}\n{}", errors.join("\n")))
+}
+
+fn write_config(path: &Path, profile: Option<&str>, values: Map<String, Value>) -> Result<(), Box<dyn Error>> {
+    let mut file: Map<String, Value> = match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data)?,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Map::new(),
+        Err(error) => Err(error)?,
+    };
+
+    // A default profile overrides the top level when loading, so it is updated instead
+    let has_default = file
+        .get("profiles")
+        .and_then(|profiles| profiles.get(DEFAULT_PROFILE))
+        .is_some();
+    let profile = profile.or_else(|| has_default.then_some(DEFAULT_PROFILE));
+
+    let target = match profile {
+        None => &mut file,
+        Some(name) => file
+            .entry("profiles")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or("profiles must be an object")?
+            .entry(name)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| format!("Profile {name} must be an object"))?,
+    };
+    target.extend(values);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    write_private(path, (serde_json::to_string_pretty(&file)? + "\n").as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::significant_drop_tightening)]
+mod tests {
+    use std::fs;
+
+    use mockito::{Matcher, Server};
+    use serde_json::{json, Map, Value};
+    use tempfile::tempdir;
+
+    use crate::config::init::{detect_api_version, write_config};
+    use crate::config::read_profile;
+
+    fn values(host: &str) -> Map<String, Value> {
+        Map::from_iter([("host".to_owned(), Value::from(host))])
+    }
+
+    #[test]
+    fn test_detect_api_version_v2() {
+        let mut server = Server::new();
+        let v3 = server
+            .mock("POST", "/api.php")
+            .match_body(Matcher::PartialJson(json!({"method": "category/search"})))
+            .with_body(r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 1}"#)
+            .create();
+        let v2 = server
+            .mock("POST", "/api.php")
+            .match_body(Matcher::PartialJson(json!({"method": "getCategories"})))
+            .with_body_from_file("tests/responses/syspass/v2/category_list.json")
+            .create();
+
+        assert_eq!(
+            Ok("SyspassV2"),
+            detect_api_version(&(server.url() + "/api.php"), "token", false)
+        );
+        v3.assert();
+        v2.assert();
+    }
+
+    #[test]
+    fn test_detect_api_version_v3() {
+        let mut server = Server::new();
+        let v3 = server
+            .mock("POST", "/api.php")
+            .match_body(Matcher::PartialJson(json!({"method": "category/search"})))
+            .with_body_from_file("tests/responses/syspass/v3/category_list.json")
+            .create();
+
+        assert_eq!(
+            Ok("SyspassV3"),
+            detect_api_version(&(server.url() + "/api.php"), "token", false)
+        );
+        v3.assert();
+    }
+
+    #[test]
+    fn test_detect_api_version_failed() {
+        let mut server = Server::new();
+        server.mock("POST", "/api.php").with_status(500).create();
+
+        assert!(detect_api_version(&(server.url() + "/api.php"), "token", false)
+            .is_err_and(|error| error.starts_with("Could not connect to")));
+    }
+
+    #[test]
+    fn test_write_config_profile() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join(".syspass/config.json");
+
+        write_config(&path, None, values("https://example.org")).expect("Failed to write config");
+        write_config(&path, Some("staging"), values("https://staging.example.org")).expect("Failed to write config");
+
+        let written: Value =
+            serde_json::from_str(&fs::read_to_string(&path).expect("Failed to read config")).expect("Invalid JSON");
+        assert_eq!(
+            json!({"host": "https://example.org", "profiles": {"staging": {"host": "https://staging.example.org"}}}),
+            written
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&path)
+                .expect("Failed to read metadata")
+                .permissions()
+                .mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+    }
+
+    #[test]
+    fn test_write_config_default_profile() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("config.json");
+        fs::write(
+            &path,
+            json!({"host": "https://top.example.org", "profiles": {"default": {"host": "https://old.example.org"}}})
+                .to_string(),
+        )
+        .expect("Failed to write config");
+
+        write_config(&path, None, values("https://new.example.org")).expect("Failed to write config");
+
+        assert_eq!(
+            Some(&Value::from("https://new.example.org")),
+            read_profile(&path, None).get("host")
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::error::Error;
+
+use clap::{ArgMatches, Command};
+use log::warn;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{Config, ValueSource, KEYS};
+use crate::list::print_table;
+use crate::output::Format;
+
+pub const COMMAND_NAME: &str = "show";
+
+const MASKED: [&str; 2] = ["token", "password"];
+
+#[derive(Serialize)]
+struct Entry {
+    key: String,
+    value: Value,
+    source: String,
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME).about("Show the effective configuration and where each value comes from")
+}
+
+pub fn command(matches: &ArgMatches, config: &Config) -> Result<u8, Box<dyn Error>> {
+    let entries = get_entries(config)?;
+    let format = Format::from(matches);
+
+    if format.is_structured() {
+        format.print_list(&entries)?;
+    } else {
+        warn!("Profile: {}", config.profile.as_deref().unwrap_or("default"));
+        print_table(
+            &["Key", "Value", "Source"],
+            entries
+                .into_iter()
+                .map(|entry| {
+                    vec![
+                        entry.key,
+                        match entry.value {
+                            Value::Null => String::new(),
+                            Value::String(value) => value,
+                            value => value.to_string(),
+                        },
+                        entry.source,
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    Ok(0)
+}
+
+fn get_entries(config: &Config) -> Result<Vec<Entry>, serde_json::Error> {
+    let values = serde_json::to_value(config)?;
+
+    Ok(KEYS
+        .iter()
+        .map(|(key, _)| {
+            let value = values.get(key).cloned().unwrap_or(Value::Null);
+            Entry {
+                key: (*key).to_owned(),
+                value: match value {
+                    Value::String(value) if MASKED.contains(key) && !value.is_empty() => {
+                        Value::String("********".to_owned())
+                    }
+                    value => value,
+                },
+                source: config.sources.get(*key).unwrap_or(&ValueSource::Default).to_string(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::config::show::get_entries;
+    use crate::config::{Config, ValueSource};
+
+    #[test]
+    fn test_get_entries() {
+        let mut config = Config {
+            host: "https://example.org".to_owned(),
+            token: "secret token".to_owned(),
+            ..Default::default()
+        };
+        config.sources.insert("host".to_owned(), ValueSource::File);
+        config
+            .sources
+            .insert("token".to_owned(), ValueSource::Env("SYSPASS_TOKEN".to_owned()));
+
+        let entries = get_entries(&config).expect("Config should serialize");
+
+        assert_eq!(16, entries.len());
+        assert_eq!(Value::String("https://example.org".to_owned()), entries[0].value);
+        assert_eq!("file", entries[0].source);
+        assert_eq!(Value::String("********".to_owned()), entries[1].value);
+        assert_eq!("env SYSPASS_TOKEN", entries[1].source);
+        assert_eq!(Value::String(String::new()), entries[2].value);
+        assert_eq!("default", entries[2].source);
+    }
+}
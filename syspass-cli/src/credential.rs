@@ -0,0 +1,260 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::process;
+
+use age::armor::ArmoredReader;
+use age::secrecy::SecretString;
+use age::Identity;
+
+use crate::agent;
+use crate::api;
+use crate::config::Config;
+use crate::prompt::ask_for_password;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Source {
+    Config,
+    Command(String),
+    File { path: String, identity: Option<String> },
+    SecretService,
+    Prompt,
+}
+
+#[derive(Debug)]
+pub struct Error(String);
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Error> for api::Error {
+    fn from(error: Error) -> Self {
+        Self::Other(error.0)
+    }
+}
+
+impl From<&Config> for Source {
+    fn from(config: &Config) -> Self {
+        if !config.password.is_empty() {
+            Self::Config
+        } else if let Some(command) = &config.password_command {
+            Self::Command(command.clone())
+        } else if let Some(path) = &config.password_file {
+            Self::File {
+                path: shellexpand::tilde(path).to_string(),
+                identity: config
+                    .password_file_identity
+                    .as_ref()
+                    .map(|identity| shellexpand::tilde(identity).to_string()),
+            }
+        } else if config.password_secret_service {
+            Self::SecretService
+        } else {
+            Self::Prompt
+        }
+    }
+}
+
+impl Source {
+    // An aborted prompt is kept apart from failures so it exits like the other prompts
+    pub fn get_password(&self, config: &Config) -> Result<String, api::Error> {
+        match self {
+            Self::Config => Ok(config.password.clone()),
+            Self::Command(command) => Ok(from_command(command)?),
+            Self::File { path, identity } => from_file(path, identity.as_deref()),
+            Self::SecretService => Ok(from_secret_service(&config.host)?),
+            Self::Prompt => from_prompt(config),
+        }
+    }
+}
+
+fn agent_key(config: &Config) -> String {
+    format!("{}@{}", config.token, config.host)
+}
+
+fn from_prompt(config: &Config) -> Result<String, api::Error> {
+    agent::get_password(&agent_key(config)).map_or_else(|| ask_for_password("API password: ", false), Ok)
+}
+
+// A prompted password only goes to the agent once the server accepted it, a rejected one is dropped from it
+pub fn verified(config: &Config, password: &str, accepted: bool) {
+    if Source::from(config) != Source::Prompt {
+        return;
+    }
+
+    if accepted {
+        agent::store_password(&agent_key(config), password);
+    } else {
+        agent::forget_password(&agent_key(config));
+    }
+}
+
+fn from_command(command: &str) -> Result<String, Error> {
+    let output = if cfg!(windows) {
+        process::Command::new("cmd").args(["/C", command]).output()
+    } else {
+        process::Command::new("sh").args(["-c", command]).output()
+    }
+    .map_err(|error| Error(format!("Failed to run password command: {error}")))?;
+
+    if !output.status.success() {
+        return Err(Error(format!(
+            "Password command failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let password = String::from_utf8(output.stdout)
+        .map_err(|_| Error("Password command did not return valid UTF-8".to_owned()))?;
+
+    Ok(password.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+fn from_file(path: &str, identity: Option<&str>) -> Result<String, api::Error> {
+    let file = File::open(path).map_err(|error| Error(format!("Failed to open password file {path}: {error}")))?;
+    let decryptor = age::Decryptor::new(ArmoredReader::new(BufReader::new(file)))
+        .map_err(|error| Error(format!("Invalid password file {path}: {error}")))?;
+
+    let identities: Vec<Box<dyn Identity>> = match identity {
+        Some(identity) => age::IdentityFile::from_file(identity.to_owned())
+            .and_then(|file| file.into_identities().map_err(std::io::Error::other))
+            .map_err(|error| Error(format!("Failed to read identity file {identity}: {error}")))?,
+        None => vec![Box::new(age::scrypt::Identity::new(SecretString::from(
+            ask_for_password("Password file passphrase: ", false)?,
+        )))],
+    };
+
+    let mut password = String::new();
+    decryptor
+        .decrypt(identities.iter().map(AsRef::as_ref))
+        .map_err(|error| Error(format!("Failed to decrypt password file {path}: {error}")))?
+        .read_to_string(&mut password)
+        .map_err(|error| Error(format!("Failed to read password file {path}: {error}")))?;
+
+    Ok(password.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+#[cfg(target_os = "linux")]
+fn from_secret_service(host: &str) -> Result<String, Error> {
+    use std::collections::HashMap;
+
+    use secret_service::blocking::SecretService;
+    use secret_service::EncryptionType;
+
+    let service = SecretService::connect(EncryptionType::Dh)
+        .map_err(|error| Error(format!("Failed to connect to Secret Service: {error}")))?;
+    let items = service
+        .search_items(HashMap::from([("application", "syspass-cli"), ("host", host)]))
+        .map_err(|error| Error(format!("Failed to search Secret Service: {error}")))?;
+
+    let item = items.unlocked.first().or_else(|| items.locked.first()).ok_or_else(|| {
+        Error(format!(
+            "No Secret Service item found for application=syspass-cli host={host}"
+        ))
+    })?;
+
+    item.ensure_unlocked()
+        .and_then(|()| item.get_secret())
+        .map_err(|error| Error(format!("Failed to read secret: {error}")))
+        .and_then(|secret| String::from_utf8(secret).map_err(|_| Error("Secret is not valid UTF-8".to_owned())))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn from_secret_service(_host: &str) -> Result<String, Error> {
+    Err(Error("Secret Service is only supported on Linux".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use age::secrecy::ExposeSecret;
+    use tempfile::NamedTempFile;
+
+    use crate::config::Config;
+    use crate::credential::Source;
+
+    #[test]
+    fn test_source_from_config() {
+        let mut config = Config {
+            password_command: Some("echo test".to_owned()),
+            password_file: Some("/tmp/password.age".to_owned()),
+            password_secret_service: true,
+            ..Default::default()
+        };
+        assert_eq!(Source::Command("echo test".to_owned()), Source::from(&config));
+
+        config.password_command = None;
+        assert_eq!(
+            Source::File {
+                path: "/tmp/password.age".to_owned(),
+                identity: None
+            },
+            Source::from(&config)
+        );
+
+        config.password_file = None;
+        assert_eq!(Source::SecretService, Source::from(&config));
+
+        config.password_secret_service = false;
+        assert_eq!(Source::Prompt, Source::from(&config));
+
+        config.password = "plain".to_owned();
+        assert_eq!(Source::Config, Source::from(&config));
+    }
+
+    #[test]
+    fn test_password_command() {
+        let config = Config {
+            password_command: Some("echo test_password".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "test_password",
+            Source::from(&config)
+                .get_password(&config)
+                .expect("Command should not have failed")
+        );
+    }
+
+    #[test]
+    fn test_password_command_failed() {
+        let config = Config {
+            password_command: Some("exit 3".to_owned()),
+            ..Default::default()
+        };
+
+        assert!(Source::from(&config).get_password(&config).is_err());
+    }
+
+    #[test]
+    fn test_password_file() {
+        let identity = age::x25519::Identity::generate();
+        let encrypted = age::encrypt(&identity.to_public(), b"file_password\n").expect("Failed to encrypt");
+
+        let mut password_file = NamedTempFile::new().expect("Failed to create file");
+        password_file.write_all(&encrypted).expect("Failed to write file");
+        let mut identity_file = NamedTempFile::new().expect("Failed to create file");
+        writeln!(identity_file, "{}", identity.to_string().expose_secret()).expect("Failed to write file");
+
+        let config = Config {
+            password_file: Some(password_file.path().to_string_lossy().to_string()),
+            password_file_identity: Some(identity_file.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "file_password",
+            Source::from(&config)
+                .get_password(&config)
+                .expect("Decrypting should not have failed")
+        );
+    }
+}
@@ -7,8 +7,10 @@ use crate::CommandError;
 
 mod category;
 mod client;
-mod edit_password;
+mod edit_account;
+pub mod edit_password;
 mod new_password;
+mod tag;
 
 pub const COMMAND_NAME_EDIT: &str = "edit";
 pub const COMMAND_NAME_NEW: &str = "new";
@@ -20,18 +22,22 @@ pub fn command_helper_edit() -> Command {
         .visible_aliases(["change"])
         .about("Edit entity")
         .subcommand_required(true)
+        .subcommand(edit_account::command_helper())
         .subcommand(edit_password::command_helper())
         .subcommand(category::command_helper())
         .subcommand(client::command_helper())
+        .subcommand(tag::command_helper())
 }
 
 #[allow(clippy::module_name_repetitions)]
 pub fn command_edit(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
     let subcommand = matches.subcommand().ok_or(CommandError::NotFound)?;
     match subcommand.0 {
+        edit_account::COMMAND_NAME => edit_account::command(subcommand.1, api_client, quiet),
         edit_password::COMMAND_NAME => edit_password::command(subcommand.1, api_client, quiet),
         category::COMMAND_NAME => category::command(subcommand.1, api_client, quiet, false),
         client::COMMAND_NAME => client::command(subcommand.1, api_client, quiet, false),
+        tag::COMMAND_NAME => tag::command(subcommand.1, api_client, quiet, false),
         _ => Err(Box::new(CommandError::NotFound)),
     }
 }
@@ -46,6 +52,7 @@ pub fn command_helper_new() -> Command {
         .subcommand(new_password::command_helper())
         .subcommand(category::command_helper())
         .subcommand(client::command_helper())
+        .subcommand(tag::command_helper())
 }
 
 pub fn command_new(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
@@ -54,6 +61,7 @@ pub fn command_new(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -
         new_password::COMMAND_NAME => new_password::command(subcommand.1, api_client, quiet),
         category::COMMAND_NAME => category::command(subcommand.1, api_client, quiet, true),
         client::COMMAND_NAME => client::command(subcommand.1, api_client, quiet, true),
+        tag::COMMAND_NAME => tag::command(subcommand.1, api_client, quiet, true),
         _ => Err(Box::new(CommandError::NotFound)),
     }
 }
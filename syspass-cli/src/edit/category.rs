@@ -7,8 +7,9 @@ use log::{info, warn};
 use crate::api;
 use crate::api::category::{ask_for, Category};
 use crate::api::entity::Entity;
-use crate::helper;
+use crate::output::Format;
 use crate::prompt::get_match_string;
+use crate::{cache, helper};
 
 pub const COMMAND_NAME: &str = "category";
 
@@ -41,7 +42,7 @@ pub fn command(
     quiet: bool,
     new: bool,
 ) -> Result<u8, Box<dyn Error>> {
-    let id = helper::get_numeric_input("id", matches, new, Some(|| ask_for(api_client).unwrap_or(0)), quiet);
+    let id = helper::get_numeric_input("id", matches, new, Some(|| ask_for(api_client)), quiet)?;
 
     edit_category(matches, api_client, id, quiet)
 }
@@ -59,7 +60,7 @@ fn edit_category(
         api_client.get_category(id)?
     };
 
-    category.set_name(get_match_string(matches, quiet, "name", "Name: ", category.name(), true).as_ref());
+    category.set_name(get_match_string(matches, quiet, "name", "Name: ", category.name(), true)?.as_ref());
     category.set_description(Some(get_match_string(
         matches,
         quiet,
@@ -67,20 +68,25 @@ fn edit_category(
         "Description: ",
         category.description().unwrap_or_default(),
         false,
-    )));
+    )?));
 
     info!("Trying to edit category");
 
     match api_client.save_category(&category) {
         Ok(category) => {
+            cache::invalidate(api_client.get_config());
             warn!(
                 "{} Category {} ({}) saved!",
                 "\u{2714}".bright_green(),
                 category.name().green(),
                 category.id().expect("Id should be set after saving")
             );
+            Format::from(matches).print_one(&category)?;
             Ok(0)
         }
-        Err(error) => Err(format!("{error}: Could not save category"))?,
+        Err(error) => {
+            warn!("{} Could not save category", "\u{2716}".bright_red());
+            Err(error)?
+        }
     }
 }
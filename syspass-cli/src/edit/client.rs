@@ -6,8 +6,9 @@ use log::{info, warn};
 
 use crate::api::client::{ask_for, Client};
 use crate::api::entity::Entity;
+use crate::output::Format;
 use crate::prompt::get_match_string;
-use crate::{api, helper};
+use crate::{api, cache, helper};
 
 pub const COMMAND_NAME: &str = "client";
 
@@ -39,13 +40,7 @@ pub fn command(
     quiet: bool,
     new: bool,
 ) -> Result<u8, Box<dyn Error>> {
-    let id = helper::get_numeric_input(
-        "id",
-        matches,
-        new,
-        Some(|| ask_for(api_client, matches).expect("Failed to get client")),
-        quiet,
-    );
+    let id = helper::get_numeric_input("id", matches, new, Some(|| ask_for(api_client, matches)), quiet)?;
     edit_client(matches, api_client, id, quiet)
 }
 
@@ -57,7 +52,7 @@ fn edit_client(matches: &ArgMatches, api_client: &dyn api::Client, id: u32, quie
         api_client.get_client(id)?
     };
 
-    client.set_name(get_match_string(matches, quiet, "name", "Name: ", client.name(), true).as_ref());
+    client.set_name(get_match_string(matches, quiet, "name", "Name: ", client.name(), true)?.as_ref());
     client.set_description(Some(get_match_string(
         matches,
         quiet,
@@ -65,20 +60,25 @@ fn edit_client(matches: &ArgMatches, api_client: &dyn api::Client, id: u32, quie
         "Description: ",
         client.description().unwrap_or_default(),
         false,
-    )));
+    )?));
 
     info!("Trying to edit client");
 
     match api_client.save_client(&client) {
         Ok(client) => {
+            cache::invalidate(api_client.get_config());
             warn!(
                 "{} Client {} ({}) saved!",
                 "\u{2714}".bright_green(),
                 client.name().green(),
                 client.id().expect("Id should be set after saving")
             );
+            Format::from(matches).print_one(&client)?;
             Ok(0)
         }
-        Err(error) => Err(format!("{error}: Could not save client"))?,
+        Err(error) => {
+            warn!("{} Could not save client", "\u{2716}".bright_red());
+            Err(error)?
+        }
     }
 }
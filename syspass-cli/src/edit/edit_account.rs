@@ -0,0 +1,244 @@
+use std::error::Error;
+
+use clap::{arg, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use dialoguer::Confirm;
+use log::{info, warn};
+
+use crate::api;
+use crate::api::account::{Account, EditAccount};
+use crate::api::entity::Entity;
+use crate::cache;
+use crate::output::Format;
+use crate::prompt::get_match_string;
+
+pub const COMMAND_NAME: &str = "account";
+
+#[allow(clippy::cognitive_complexity)]
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Edit account details. Requires permissions: [Edit Account]")
+        .arg(
+            arg!(-i --id <ID> "Account ID")
+                .required(true)
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-n --name <NAME> "Account name")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-u --url <URL> "Url for site")
+                .required(false)
+                .value_hint(ValueHint::Url),
+        )
+        .arg(
+            arg!(-l --login <LOGIN> "Username")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-o --note <NOTES> "Notes text")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-a --category <CATEGORYID> "Category id")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--client <CLIENTID> "Client id")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn api::Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
+    let id = matches.get_one::<u32>("id").copied().expect("Id is required");
+    let original = api_client.view_account(id)?;
+
+    let updated = Account::new(
+        Some(id),
+        get_match_string(matches, quiet, "name", "Name: ", original.name(), true)?,
+        get_match_string(matches, quiet, "login", "Username: ", original.login(), false)?,
+        Some(get_match_string(
+            matches,
+            quiet,
+            "url",
+            "Url: ",
+            original.url().unwrap_or_default(),
+            false,
+        )?),
+        Some(get_match_string(
+            matches,
+            quiet,
+            "note",
+            "Notes: ",
+            original.notes().unwrap_or_default(),
+            false,
+        )?),
+        get_entity_id(matches, quiet, "category", *original.category_id(), || {
+            api::category::ask_for(api_client)
+        })?,
+        get_entity_id(matches, quiet, "client", *original.client_id(), || {
+            api::client::ask_for(api_client, matches)
+        })?,
+        None,
+        None,
+    );
+
+    if !has_changes(&original, &updated) {
+        warn!("{} Nothing to change", "\u{2714}".bright_green());
+        return Ok(0);
+    }
+
+    info!("Trying to edit account");
+
+    match api_client.edit_account(&get_edit(&original, &updated)) {
+        Ok(account) => {
+            cache::invalidate(api_client.get_config());
+            warn!(
+                "{} Account {} ({}) saved!",
+                "\u{2714}".bright_green(),
+                account.name().green(),
+                account.id().expect("Id should be set after saving")
+            );
+            Format::from(matches).print_one(&account)?;
+            Ok(0)
+        }
+        Err(error) => {
+            warn!("{} Could not save account", "\u{2716}".bright_red());
+            Err(error)?
+        }
+    }
+}
+
+fn get_entity_id<F>(matches: &ArgMatches, quiet: bool, field: &str, current: u32, ask: F) -> Result<u32, api::Error>
+where
+    F: FnOnce() -> Result<u32, api::Error>,
+{
+    if let Some(id) = matches.get_one::<u32>(field) {
+        return Ok(*id);
+    }
+
+    if quiet
+        || !Confirm::new()
+            .with_prompt(format!("Change {field} (current id {current})?"))
+            .default(false)
+            .interact()
+            .unwrap_or(false)
+    {
+        return Ok(current);
+    }
+
+    ask()
+}
+
+fn has_changes(original: &Account, updated: &Account) -> bool {
+    original.name() != updated.name()
+        || original.category_id() != updated.category_id()
+        || original.client_id() != updated.client_id()
+        || original.login() != updated.login()
+        || original.url().unwrap_or_default() != updated.url().unwrap_or_default()
+        || original.notes().unwrap_or_default() != updated.notes().unwrap_or_default()
+}
+
+// Expiry can't be edited here but would be lost if it wasn't sent back.
+// account/view returns no tags, so none are sent and sysPass keeps the current ones
+fn get_edit(original: &Account, updated: &Account) -> EditAccount {
+    EditAccount {
+        id: *original.id().expect("Id should be set"),
+        name: updated.name().to_owned(),
+        category_id: *updated.category_id(),
+        client_id: *updated.client_id(),
+        login: updated.login().to_owned(),
+        url: updated.url().unwrap_or_default().to_owned(),
+        notes: updated.notes().unwrap_or_default().to_owned(),
+        tags_id: Vec::new(),
+        expire_date: original.expire_date().unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::account::Account;
+    use crate::edit::edit_account::{get_edit, has_changes};
+
+    fn get_test_account() -> Account {
+        Account::new(
+            Some(3),
+            "test".to_owned(),
+            "login".to_owned(),
+            Some("example.org".to_owned()),
+            None,
+            1,
+            2,
+            None,
+            Some("client".to_owned()),
+        )
+    }
+
+    #[test]
+    fn test_has_changes_nothing_changed() {
+        let original = get_test_account();
+        let updated = Account::new(
+            Some(3),
+            "test".to_owned(),
+            "login".to_owned(),
+            Some("example.org".to_owned()),
+            Some(String::new()),
+            1,
+            2,
+            None,
+            None,
+        );
+
+        assert!(!has_changes(&original, &updated));
+    }
+
+    #[test]
+    fn test_get_edit() {
+        let original = get_test_account();
+        let updated = Account::new(
+            Some(3),
+            "test".to_owned(),
+            "new login".to_owned(),
+            Some("example.org".to_owned()),
+            Some("new notes".to_owned()),
+            5,
+            2,
+            None,
+            None,
+        );
+
+        assert!(has_changes(&original, &updated));
+        let edit = get_edit(&original, &updated);
+
+        assert_eq!(3, edit.id);
+        assert_eq!("test", edit.name);
+        assert_eq!(5, edit.category_id);
+        assert_eq!(2, edit.client_id);
+        assert_eq!("new login", edit.login);
+        assert_eq!("example.org", edit.url);
+        assert_eq!("new notes", edit.notes);
+    }
+
+    #[test]
+    fn test_get_edit_keeps_expiry() {
+        let original: Account = serde_json::from_value(serde_json::json!({
+            "id": 3, "name": "test", "login": "login", "categoryId": 1, "clientId": 2,
+            "passDateChange": 1_900_000_000,
+        }))
+        .expect("Valid account");
+
+        let edit = get_edit(&original, &original);
+
+        assert!(edit.tags_id.is_empty());
+        assert_eq!(1_900_000_000, edit.expire_date);
+    }
+}
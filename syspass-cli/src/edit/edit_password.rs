@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
-use chrono::{NaiveDateTime, Utc};
+use chrono::{NaiveDate, Utc};
 use clap::{arg, ArgMatches, Command, ValueHint};
 use colored::Colorize;
 use dialoguer::{theme::ColorfulTheme, Select};
@@ -11,8 +11,15 @@ use passwords::analyzer;
 use passwords::scorer;
 use passwords::PasswordGenerator;
 
+use crate::api;
 use crate::api::account::ChangePassword;
-use crate::prompt::{ask_for_date, ask_for_password, password_strength};
+use crate::api::Client;
+use crate::generate::passphrase::{entropy_strength, Passphrase};
+use crate::generate::{get_policy, Policy};
+use crate::helper::{end_of_day, parse_date};
+use crate::output::Format;
+use crate::prompt::{ask_for_checked_password, ask_for_date, password_strength};
+use crate::strength::{enforce, Checks, Requirements};
 
 pub const COMMAND_NAME: &str = "password";
 
@@ -34,9 +41,8 @@ impl ChangeAccountArgs {
                 .map_or("", String::as_str)
                 .to_owned(),
             expiration_date: matches
-                .get_one::<String>("expiration")
-                .map_or("", |s| s.as_str())
-                .to_owned(),
+                .get_one::<NaiveDate>("expiration")
+                .map_or_else(String::new, |date| end_of_day(*date).to_string()),
         }
     }
 }
@@ -45,7 +51,7 @@ impl ChangeAccountArgs {
 pub fn command_helper() -> Command {
     Command::new(COMMAND_NAME)
         .about("Change account password. Requires permissions: [Edit Account Password]")
-        .visible_aliases(["account", "pass"])
+        .visible_aliases(["pass"])
         .short_flag('p')
         .arg(
             arg!(-i --id <ID> "Account ID")
@@ -60,17 +66,43 @@ pub fn command_helper() -> Command {
         )
         .arg(
             arg!(-e --expiration <EXPIRATION> "Expiration YYYY-mm-dd")
+                .required(false)
+                .value_parser(parse_date)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-P --policy <NAME> "Password policy for the suggestions, defaults to passwordPolicy")
                 .required(false)
                 .value_hint(ValueHint::Other),
         )
+        .arg(arg!(--"allow-weak" "Save a password that doesn't meet passwordRequirements"))
 }
 
-pub fn command(matches: &ArgMatches, api_client: &dyn crate::api::Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
-    let args: ChangeAccountArgs = get_args(matches, quiet);
+// The account is only looked up when a category has its own requirements
+fn get_checks(api_client: &dyn Client, id: u32) -> Result<Checks<'_>, Box<dyn Error>> {
+    let config = api_client.get_config();
+    let category = match &config.password_requirements {
+        Some(requirements) if !requirements.categories.is_empty() => Some(*api_client.view_account(id)?.category_id()),
+        _ => None,
+    };
+
+    Ok(Checks::new(config, category)?)
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
+    let policy = get_policy(
+        api_client.get_config(),
+        matches.get_one::<String>("policy").map(String::as_str),
+    )?;
+    let id = *matches.get_one::<u32>("id").expect("Id is required");
+    let checks = get_checks(api_client, id)?;
+    let allow_weak = matches.get_flag("allow-weak");
+    let args: ChangeAccountArgs = get_args(matches, quiet, policy, Some(&checks).filter(|_| !allow_weak))?;
 
     if args.password.is_empty() {
         Err("Password can't be empty")?;
     }
+    enforce(&checks, &args.password, allow_weak)?;
 
     let change = ChangePassword {
         id: args.id,
@@ -87,6 +119,7 @@ pub fn command(matches: &ArgMatches, api_client: &dyn crate::api::Client, quiet:
                 "\u{2714}".bright_green(),
                 format!("{account}").green()
             );
+            Format::from(matches).print_one(&account)?;
         }
         Err(error) => Err(error)?,
     }
@@ -94,31 +127,26 @@ pub fn command(matches: &ArgMatches, api_client: &dyn crate::api::Client, quiet:
     Ok(0)
 }
 
-fn get_args(matches: &ArgMatches, quiet: bool) -> ChangeAccountArgs {
+fn get_args(
+    matches: &ArgMatches,
+    quiet: bool,
+    policy: Option<&Policy>,
+    checks: Option<&Checks>,
+) -> Result<ChangeAccountArgs, api::Error> {
     let mut args: ChangeAccountArgs = ChangeAccountArgs::new(matches);
 
     if args.password.is_empty() && !quiet {
-        args.password = get_password("New password:");
+        args.password = get_password("New password:", policy, checks)?;
     }
 
-    if args.expiration_date.is_empty() {
-        if !quiet {
-            let date = Utc::now()
-                .checked_add_months(chrono::Months::new(18))
-                .map_or_else(|| panic!("Could not modify date"), |date| date.date_naive());
+    if args.expiration_date.is_empty() && !quiet {
+        let today = Utc::now().date_naive();
+        let date = today.checked_add_months(chrono::Months::new(18)).unwrap_or(today);
 
-            args.expiration_date = ask_for_date("Expiration date:", date);
-        }
-    } else {
-        let expiration = args.expiration_date + "23:59:59";
-        args.expiration_date = NaiveDateTime::parse_from_str(&expiration, "%Y-%m-%d %H:%M:%S")
-            .expect("Failed to parse expiration date")
-            .and_utc()
-            .timestamp()
-            .to_string();
+        args.expiration_date = ask_for_date("Expiration date:", date);
     }
 
-    args
+    Ok(args)
 }
 
 struct PasswordData {
@@ -141,7 +169,7 @@ impl Display for PasswordData {
 
 struct GeneratorParams(usize, bool, bool);
 
-fn generate_passwords(random_count: usize) -> Vec<PasswordData> {
+fn generate_passwords(random_count: usize, policy: Option<&Policy>) -> Vec<PasswordData> {
     let mut suggest: Vec<String> = Vec::new();
 
     let params = [
@@ -170,8 +198,14 @@ fn generate_passwords(random_count: usize) -> Vec<PasswordData> {
         );
     }
 
-    for generator in generators {
-        suggest.append(&mut generator.generate(random_count).expect("Password generator failed"));
+    if let Some(policy) = policy {
+        for _ in 0..random_count * generators.len() {
+            suggest.push(policy.generate().expect("Password generator failed"));
+        }
+    } else {
+        for generator in generators {
+            suggest.append(&mut generator.generate(random_count).expect("Password generator failed"));
+        }
     }
 
     let mut pairs: Vec<PasswordData> = vec![];
@@ -195,8 +229,49 @@ fn generate_passwords(random_count: usize) -> Vec<PasswordData> {
     pairs
 }
 
-pub fn get_password(prompt: &str) -> String {
-    let pairs: Vec<PasswordData> = generate_passwords(5);
+// Passphrases are rated by their entropy, which isn't comparable to the score of the passwords
+fn generate_passphrases(count: usize) -> Vec<PasswordData> {
+    let passphrase = Passphrase::default();
+    let entropy = passphrase.entropy();
+
+    (0..count)
+        .map(|_| PasswordData {
+            password: passphrase.generate(),
+            strength: entropy_strength(entropy),
+            strength_value: entropy,
+        })
+        .collect()
+}
+
+// The strongest of the generated suggestions
+pub fn generate_password(policy: Option<&Policy>) -> String {
+    generate_passwords(1, policy)
+        .into_iter()
+        .next()
+        .map(|pair| pair.password)
+        .expect("Password generator failed")
+}
+
+// Passphrases can't follow a password policy's length and character classes, so they are only offered without one
+fn get_suggestions(policy: Option<&Policy>, requirements: Option<&Requirements>) -> Vec<PasswordData> {
+    let mut pairs: Vec<PasswordData> = generate_passwords(5, policy);
+    if policy.is_none() {
+        // Right after the strongest password so they show up without scrolling
+        pairs.splice(1..1, generate_passphrases(3));
+    }
+    pairs.retain(|pair| requirements.is_none_or(|requirements| requirements.check(&pair.password).is_ok()));
+
+    pairs
+}
+
+// Suggestions are checked against the requirements, typed passwords also against the breach file
+pub fn get_password(prompt: &str, policy: Option<&Policy>, checks: Option<&Checks>) -> Result<String, api::Error> {
+    let check = |password: &str| checks.map_or(Ok(()), |checks| checks.check(password));
+    let pairs = get_suggestions(policy, checks.and_then(|checks| checks.requirements));
+    if pairs.is_empty() {
+        return ask_for_checked_password(prompt, true, check);
+    }
+
     Select::with_theme(&ColorfulTheme::default())
         .with_prompt("Choose password (Press ESC to enter your own):")
         .default(0)
@@ -204,20 +279,21 @@ pub fn get_password(prompt: &str) -> String {
         .report(false)
         .max_length(10)
         .interact_opt()
-        .expect("Failed to select password")
+        .map_err(|_| api::Error::Aborted)?
         .map_or_else(
-            || ask_for_password(prompt, true),
-            |choice| pairs[choice].password.clone(),
+            || ask_for_checked_password(prompt, true, check),
+            |choice| Ok(pairs[choice].password.clone()),
         )
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::edit::edit_password::{generate_passwords, PasswordData};
+    use crate::edit::edit_password::{generate_passphrases, generate_passwords, get_suggestions, PasswordData};
+    use crate::generate::Policy;
 
     #[test]
     fn test_generate_passwords() {
-        let passwords = generate_passwords(5);
+        let passwords = generate_passwords(5, None);
 
         assert_eq!(5 * 8, passwords.len());
 
@@ -230,6 +306,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generate_passwords_with_policy() {
+        let policy = Policy {
+            length: 10,
+            symbols: false,
+            ..Default::default()
+        };
+        let passwords = generate_passwords(5, Some(&policy));
+
+        assert_eq!(5 * 8, passwords.len());
+        assert!(passwords
+            .iter()
+            .all(|pair| pair.password.len() == 10 && pair.password.chars().all(char::is_alphanumeric)));
+    }
+
+    #[test]
+    fn test_generate_passphrases() {
+        let passphrases = generate_passphrases(3);
+
+        assert_eq!(3, passphrases.len());
+        // The strength is the entropy of the six words and the digit of the generator
+        assert!(passphrases.iter().all(|pair| pair.strength == "83 bits"));
+    }
+
+    #[test]
+    fn test_get_suggestions() {
+        let is_passphrase = |pair: &PasswordData| pair.strength == "83 bits";
+
+        let suggestions = get_suggestions(None, None);
+        assert_eq!(5 * 8 + 3, suggestions.len());
+        assert!(suggestions[1..4].iter().all(is_passphrase));
+
+        let policy = Policy {
+            length: 12,
+            symbols: false,
+            ..Default::default()
+        };
+        let suggestions = get_suggestions(Some(&policy), None);
+        assert_eq!(5 * 8, suggestions.len());
+        assert!(suggestions
+            .iter()
+            .all(|pair| pair.password.len() == 12 && pair.password.chars().all(char::is_alphanumeric)));
+    }
+
     #[test]
     fn test_display_password() {
         assert_eq!(
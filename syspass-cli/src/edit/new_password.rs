@@ -1,15 +1,17 @@
 use std::error::Error;
-use std::process;
 
 use clap::{arg, ArgMatches, Command, ValueHint};
 use colored::Colorize;
-use log::{error, warn};
+use log::warn;
 
 use crate::api::account::Account;
 use crate::api::entity::Entity;
 use crate::edit::edit_password::get_password;
+use crate::generate::get_policy;
+use crate::output::Format;
 use crate::prompt::get_match_string;
-use crate::{api, helper};
+use crate::strength::{enforce, Checks};
+use crate::{api, cache, helper};
 
 pub const COMMAND_NAME: &str = "password";
 
@@ -63,58 +65,75 @@ pub fn command_helper() -> Command {
                 .required(false)
                 .value_hint(ValueHint::Other),
         )
+        .arg(
+            arg!(-P --policy <NAME> "Password policy for the suggestions, defaults to passwordPolicy")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(arg!(--"allow-weak" "Save a password that doesn't meet passwordRequirements"))
 }
 
 pub fn command(matches: &ArgMatches, api_client: &dyn api::Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
+    let policy = get_policy(
+        api_client.get_config(),
+        matches.get_one::<String>("policy").map(String::as_str),
+    )?;
+    let name = get_match_string(matches, quiet, "name", "Name: ", "", true)?;
+    let login = get_match_string(matches, quiet, "login", "Username: ", "", false)?;
+    let url = get_match_string(matches, quiet, "url", "Url: ", "", false)?;
+    let notes = get_match_string(matches, quiet, "note", "Notes: ", "", false)?;
+    let category = helper::get_numeric_input(
+        "category",
+        matches,
+        false,
+        Some(|| api::category::ask_for(api_client)),
+        quiet,
+    )?;
+    let client = helper::get_numeric_input(
+        "client",
+        matches,
+        false,
+        Some(|| api::client::ask_for(api_client, matches)),
+        quiet,
+    )?;
+
+    let checks = Checks::new(api_client.get_config(), Some(category))?;
+    let allow_weak = matches.get_flag("allow-weak");
+    let password = match matches.get_one::<String>("password") {
+        Some(password) => password.clone(),
+        None if quiet => Err(api::Error::Other("Could not ask for password".to_owned()))?,
+        None => get_password("Password: ", policy, Some(&checks).filter(|_| !allow_weak))?,
+    };
+    enforce(&checks, &password, allow_weak)?;
+
     let account: Account = Account::new(
         Some(0),
-        get_match_string(matches, quiet, "name", "Name: ", "", true),
-        get_match_string(matches, quiet, "login", "Username: ", "", false),
-        Some(get_match_string(matches, quiet, "url", "Url: ", "", false)),
-        Some(get_match_string(matches, quiet, "note", "Notes: ", "", false)),
-        helper::get_numeric_input(
-            "category",
-            matches,
-            false,
-            Some(|| {
-                api::category::ask_for(api_client).unwrap_or_else(|error| {
-                    error!("{} {}", "\u{2716}".bright_red(), error.to_string());
-                    process::exit(1);
-                })
-            }),
-            quiet,
-        ),
-        helper::get_numeric_input(
-            "client",
-            matches,
-            false,
-            Some(|| api::client::ask_for(api_client, matches).expect("Failed to get client")),
-            quiet,
-        ),
-        Some(matches.get_one::<String>("password").map_or_else(
-            || {
-                if quiet {
-                    warn!("Could not ask for client");
-                    process::exit(1);
-                }
-                get_password("Password: ")
-            },
-            std::clone::Clone::clone,
-        )),
+        name,
+        login,
+        Some(url),
+        Some(notes),
+        category,
+        client,
+        Some(password),
         None,
     );
 
     warn!("Trying to save account");
     match api_client.save_account(&account) {
         Ok(account) => {
+            cache::invalidate(api_client.get_config());
             warn!(
                 "{} Account {} ({}) saved!",
                 "\u{2714}".bright_green(),
                 account.name().green(),
                 account.id().expect("Id should not be empty")
             );
+            Format::from(matches).print_one(&account)?;
             Ok(0)
         }
-        Err(error) => Err(format!("Could not save client: {error}"))?,
+        Err(error) => {
+            warn!("{} Could not save account", "\u{2716}".bright_red());
+            Err(error)?
+        }
     }
 }
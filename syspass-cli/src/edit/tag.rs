@@ -0,0 +1,72 @@
+use std::error::Error;
+
+use clap::{arg, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::{info, warn};
+
+use crate::api;
+use crate::api::entity::Entity;
+use crate::api::tag::{ask_for, Tag};
+use crate::helper;
+use crate::output::Format;
+use crate::prompt::get_match_string;
+
+pub const COMMAND_NAME: &str = "tag";
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Edit tag")
+        .short_flag('t')
+        .arg(
+            arg!(-i --id <ID> "Tag ID. Leave empty for new")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-n --name <NAME> "New name")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+}
+
+pub fn command(
+    matches: &ArgMatches,
+    api_client: &dyn api::Client,
+    quiet: bool,
+    new: bool,
+) -> Result<u8, Box<dyn Error>> {
+    let id = helper::get_numeric_input("id", matches, new, Some(|| ask_for(api_client)), quiet)?;
+
+    edit_tag(matches, api_client, id, quiet)
+}
+
+fn edit_tag(matches: &ArgMatches, api_client: &dyn api::Client, id: u32, quiet: bool) -> Result<u8, Box<dyn Error>> {
+    let mut tag: Tag = if id == 0 {
+        warn!("Creating a new tag");
+        Tag::default()
+    } else {
+        api_client.get_tag(id)?
+    };
+
+    tag.set_name(get_match_string(matches, quiet, "name", "Name: ", tag.name(), true)?.as_ref());
+
+    info!("Trying to edit tag");
+
+    match api_client.save_tag(&tag) {
+        Ok(tag) => {
+            warn!(
+                "{} Tag {} ({}) saved!",
+                "\u{2714}".bright_green(),
+                tag.name().green(),
+                tag.id().expect("Id should be set after saving")
+            );
+            Format::from(matches).print_one(&tag)?;
+            Ok(0)
+        }
+        Err(error) => {
+            warn!("{} Could not save tag", "\u{2716}".bright_red());
+            Err(error)?
+        }
+    }
+}
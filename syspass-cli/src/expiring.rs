@@ -0,0 +1,157 @@
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use clap::{arg, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::warn;
+use serde::Serialize;
+
+use crate::api::account::Account;
+use crate::api::entity::Entity;
+use crate::api::Client;
+use crate::helper::{parse_days, DAY};
+use crate::list::print_table;
+use crate::output::Format;
+
+pub const COMMAND_NAME: &str = "expiring";
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct Expiring {
+    id: u32,
+    name: String,
+    login: String,
+    client_name: Option<String>,
+    changed: Option<String>,
+    expires: String,
+    days_left: i64,
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("List accounts whose password has expired or expires soon. Requires sysPass 3")
+        .arg(
+            arg!(-w --within <DAYS> "Also list passwords expiring within this many days, e.g. 30d or 4w")
+                .required(false)
+                .default_value("30d")
+                .value_parser(parse_days)
+                .value_hint(ValueHint::Other),
+        )
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client) -> Result<u8, Box<dyn Error>> {
+    let within = *matches.get_one::<u32>("within").expect("Within has a default");
+    let expiring = get_expiring(&api_client.get_expiring_accounts()?, Utc::now().timestamp(), within);
+
+    let format = Format::from(matches);
+    if format.is_structured() {
+        format.print_list(&expiring)?;
+    } else if expiring.is_empty() {
+        warn!("{} No passwords expire within {within} days", "\u{2714}".bright_green());
+    } else {
+        print_table(
+            &["Id", "Name", "Login", "Client", "Expires", "Status"],
+            expiring
+                .iter()
+                .map(|account| {
+                    vec![
+                        account.id.to_string(),
+                        account.name.clone(),
+                        account.login.clone(),
+                        account.client_name.clone().unwrap_or_default(),
+                        account.expires.clone(),
+                        status(account.days_left),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    Ok(0)
+}
+
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0).map_or_else(String::new, |date| date.format("%Y-%m-%d").to_string())
+}
+
+fn status(days_left: i64) -> String {
+    match days_left {
+        ..=-1 => format!("expired {} day(s) ago", -days_left).bright_red().to_string(),
+        0 => "expires today".bright_red().to_string(),
+        _ => format!("in {days_left} day(s)").yellow().to_string(),
+    }
+}
+
+// Expired passwords and those expiring before now + within days, oldest first
+fn get_expiring(accounts: &[Account], now: i64, within: u32) -> Vec<Expiring> {
+    let limit = now + i64::from(within) * DAY;
+    let mut expiring: Vec<_> = accounts
+        .iter()
+        .filter_map(|account| {
+            account
+                .expire_date()
+                .filter(|date| *date <= limit)
+                .map(|date| (date, account))
+        })
+        .collect();
+    expiring.sort_by_key(|(date, account)| (*date, account.id().copied()));
+
+    expiring
+        .into_iter()
+        .map(|(date, account)| Expiring {
+            id: account.id().copied().unwrap_or_default(),
+            name: account.name().to_owned(),
+            login: account.login().to_owned(),
+            client_name: account.client_name().map(ToOwned::to_owned),
+            changed: account.pass_date().map(format_date),
+            expires: format_date(date),
+            days_left: (date - now).div_euclid(DAY),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::account::Account;
+    use crate::expiring::get_expiring;
+    use crate::helper::DAY;
+
+    fn account(id: u32, pass_date_change: i64) -> Account {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": format!("account {id}"),
+            "login": "login",
+            "categoryId": 1,
+            "clientId": 1,
+            "passDate": 1_690_000_000,
+            "passDateChange": pass_date_change,
+        }))
+        .expect("Deserialization failed")
+    }
+
+    #[test]
+    fn test_get_expiring() {
+        let now = 1_700_000_000;
+        let accounts = vec![
+            account(1, now + 40 * DAY),
+            account(2, now + 10 * DAY),
+            account(3, 0),
+            account(4, now - 3 * DAY),
+            account(5, now + 30 * DAY),
+        ];
+
+        let expiring = get_expiring(&accounts, now, 30);
+
+        assert_eq!(
+            vec![4, 2, 5],
+            expiring.iter().map(|account| account.id).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![-3, 10, 30],
+            expiring.iter().map(|account| account.days_left).collect::<Vec<_>>()
+        );
+        assert_eq!("2023-11-11", expiring[0].expires);
+        assert_eq!(Some("2023-07-22".to_owned()), expiring[0].changed);
+        assert_eq!(1, get_expiring(&accounts, now, 0).len());
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+
+use age::armor::{ArmoredWriter, Format as ArmorFormat};
+use age::secrecy::SecretString;
+use clap::{arg, ArgAction, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::api::entity::Entity;
+use crate::api::{self, Client};
+use crate::config;
+use crate::prompt::ask_for_password;
+use crate::LOG_TO_STDERR;
+
+pub const COMMAND_NAME: &str = "export";
+
+const CSV: &str = "csv";
+const KEEPASS: &str = "keepass";
+const JSON: &str = "json";
+const PASSPHRASE_ENV: &str = "SYSPASS_EXPORT_PASSPHRASE";
+
+#[derive(Serialize)]
+struct Record {
+    id: u32,
+    name: String,
+    login: String,
+    password: String,
+    url: String,
+    notes: String,
+    category: String,
+    client: String,
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Export accounts with their passwords to an encrypted file")
+        .arg(
+            arg!(<FILE> "File to write, - for stdout")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_hint(ValueHint::FilePath),
+        )
+        .arg(
+            arg!(-f --format <FORMAT> "File format, guessed from the extension by default")
+                .required(false)
+                .value_parser([CSV, KEEPASS, JSON]),
+        )
+        .arg(
+            arg!(-a --category <CATEGORYID> "Only export accounts of this category")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-i --client <CLIENTID> "Only export accounts of this client")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-r --recipient <RECIPIENT> "Encrypt to an age public key instead of a passphrase")
+                .required(false)
+                .action(ArgAction::Append)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-R --"recipients-file" <FILE> "Encrypt to the age public keys listed in a file")
+                .required(false)
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_hint(ValueHint::FilePath),
+        )
+        .arg(
+            arg!(--"insecure-plaintext" "Write the passwords without encryption")
+                .conflicts_with_all(["recipient", "recipients-file"]),
+        )
+}
+
+fn get_format<'a>(file: &Path, format: Option<&'a str>) -> &'a str {
+    format.unwrap_or_else(|| {
+        let name = file.to_string_lossy();
+        let name = name.strip_suffix(".age").unwrap_or(&name);
+        match Path::new(name).extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => CSV,
+            Some("xml") => KEEPASS,
+            _ => JSON,
+        }
+    })
+}
+
+fn get_recipients(matches: &ArgMatches) -> Result<Vec<age::x25519::Recipient>, Box<dyn Error>> {
+    let mut keys: Vec<String> = matches
+        .get_many::<String>("recipient")
+        .map(|keys| keys.cloned().collect())
+        .unwrap_or_default();
+
+    if let Some(file) = matches.get_one::<PathBuf>("recipients-file") {
+        let data = fs::read_to_string(file).map_err(|error| format!("Could not read {}: {error}", file.display()))?;
+        keys.extend(
+            data.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(ToOwned::to_owned),
+        );
+    }
+
+    Ok(keys
+        .iter()
+        .map(|key| age::x25519::Recipient::from_str(key).map_err(|error| format!("Invalid recipient {key}: {error}")))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+fn get_passphrase(quiet: bool) -> Result<SecretString, api::Error> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => Ok(SecretString::from(passphrase)),
+        _ if quiet => Err(api::Error::Other(format!(
+            "Could not ask for the export passphrase, set {PASSPHRASE_ENV} or use --recipient"
+        ))),
+        _ => Ok(SecretString::from(ask_for_password("Export passphrase: ", true)?)),
+    }
+}
+
+fn encrypt(data: &[u8], encryptor: age::Encryptor) -> io::Result<Vec<u8>> {
+    let mut output = vec![];
+    let armor = ArmoredWriter::wrap_output(&mut output, ArmorFormat::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armor)?;
+    writer.write_all(data)?;
+    writer.finish()?.finish()?;
+
+    Ok(output)
+}
+
+fn fetch(matches: &ArgMatches, api_client: &dyn Client) -> Result<Vec<Record>, api::Error> {
+    let mut search = vec![("count", api::ACCOUNT_LIMIT.to_owned())];
+    for (field, key) in [("category", "categoryId"), ("client", "clientId")] {
+        if let Some(id) = matches.get_one::<u32>(field) {
+            search.push((key, id.to_string()));
+        }
+    }
+
+    let categories: HashMap<u32, String> = api_client
+        .get_categories()?
+        .iter()
+        .filter_map(|category| category.id().map(|id| (*id, category.name().to_owned())))
+        .collect();
+    let clients: HashMap<u32, String> = api_client
+        .get_clients()?
+        .iter()
+        .filter_map(|client| client.id().map(|id| (*id, client.name().to_owned())))
+        .collect();
+
+    // An escrow copy is only useful when it is complete, so any failing account aborts the export
+    api_client
+        .search_account(search, false)?
+        .iter()
+        .map(|account| {
+            let password = api_client.get_password(account)?.password;
+            info!("Exported {}", account.name());
+
+            Ok(Record {
+                id: account.id().copied().unwrap_or_default(),
+                name: account.name().to_owned(),
+                login: account.login().to_owned(),
+                password,
+                url: account.url().unwrap_or_default().to_owned(),
+                notes: account.notes().unwrap_or_default().to_owned(),
+                category: categories.get(account.category_id()).cloned().unwrap_or_default(),
+                client: clients.get(account.client_id()).cloned().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn to_csv(records: &[Record]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for record in records {
+        writer.serialize(record)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
+// XML 1.0 can't hold most control characters, not even as character references
+const fn is_xml_char(char: char) -> bool {
+    matches!(char, '\t' | '\n' | '\r' | '\u{20}'..='\u{fffd}' | '\u{10000}'..)
+        && !matches!(char, '\u{fffe}' | '\u{ffff}')
+}
+
+// KeePass refuses the whole file over a single invalid character, so they are left out
+fn escape(value: &str) -> String {
+    value
+        .chars()
+        .filter(|char| is_xml_char(*char))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn push_group(xml: &mut String, indent: &str, name: &str) {
+    let _ = writeln!(xml, "{indent}<Group>\n{indent}\t<Name>{}</Name>", escape(name));
+}
+
+type Groups<'a> = BTreeMap<&'a str, BTreeMap<&'a str, Vec<&'a Record>>>;
+
+// Accounts are grouped as client / category, the layout `import` reads back.
+// A password losing a character would be a different password, so those accounts stop the export
+fn to_keepass(records: &[Record]) -> Result<String, String> {
+    let mut groups: Groups = BTreeMap::new();
+    for record in records {
+        if !record.password.chars().all(is_xml_char) {
+            return Err(format!(
+                "The password of {}. {} has a control character KeePass XML can't hold, export as csv or json",
+                record.id, record.name
+            ));
+        }
+        if ![
+            &record.name,
+            &record.login,
+            &record.url,
+            &record.notes,
+            &record.category,
+            &record.client,
+        ]
+        .iter()
+        .all(|value| value.chars().all(is_xml_char))
+        {
+            warn!("Removed control characters from {}. {}", record.id, record.name);
+        }
+        groups
+            .entry(&record.client)
+            .or_default()
+            .entry(&record.category)
+            .or_default()
+            .push(record);
+    }
+
+    let mut xml = "<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile>\n\t<Meta>\n\
+        \t\t<Generator>syspass-cli</Generator>\n\t</Meta>\n\t<Root>\n"
+        .to_owned();
+    push_group(&mut xml, "\t\t", "sysPass");
+    for (client, categories) in groups {
+        push_group(&mut xml, "\t\t\t", client);
+        for (category, records) in categories {
+            push_group(&mut xml, "\t\t\t\t", category);
+            for record in records {
+                xml.push_str("\t\t\t\t\t<Entry>\n");
+                for (key, value) in [
+                    ("Title", &record.name),
+                    ("UserName", &record.login),
+                    ("Password", &record.password),
+                    ("URL", &record.url),
+                    ("Notes", &record.notes),
+                ] {
+                    let _ = writeln!(
+                        xml,
+                        "\t\t\t\t\t\t<String><Key>{key}</Key><Value>{}</Value></String>",
+                        escape(value)
+                    );
+                }
+                xml.push_str("\t\t\t\t\t</Entry>\n");
+            }
+            xml.push_str("\t\t\t\t</Group>\n");
+        }
+        xml.push_str("\t\t\t</Group>\n");
+    }
+    xml.push_str("\t\t</Group>\n\t</Root>\n</KeePassFile>\n");
+
+    Ok(xml)
+}
+
+fn serialize(records: &[Record], format: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(match format {
+        CSV => to_csv(records)?,
+        KEEPASS => to_keepass(records)?.into_bytes(),
+        _ => (serde_json::to_string_pretty(records)? + "\n").into_bytes(),
+    })
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
+    let file = matches.get_one::<PathBuf>("FILE").expect("File is required");
+    if file.as_os_str() == "-" {
+        // Anything logged to stdout would end up inside the export
+        LOG_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+    let format = get_format(file, matches.get_one::<String>("format").map(String::as_str));
+    let plaintext = matches.get_flag("insecure-plaintext");
+    let recipients = get_recipients(matches)?;
+
+    let encryptor = if plaintext {
+        None
+    } else if recipients.is_empty() {
+        Some(age::Encryptor::with_user_passphrase(get_passphrase(quiet)?))
+    } else {
+        Some(age::Encryptor::with_recipients(
+            recipients.iter().map(|recipient| -> &dyn age::Recipient { recipient }),
+        )?)
+    };
+
+    let records = fetch(matches, api_client)?;
+    let data = serialize(&records, format)?;
+    let data = if let Some(encryptor) = encryptor {
+        encrypt(&data, encryptor)?
+    } else {
+        warn!("{} Writing passwords without encryption", "\u{26a0}".bright_yellow());
+        data
+    };
+
+    if file.as_os_str() == "-" {
+        io::stdout().write_all(&data)?;
+    } else {
+        config::write_private(file, &data).map_err(|error| format!("Could not write {}: {error}", file.display()))?;
+    }
+
+    warn!(
+        "{} Exported {} account(s) to {}",
+        "\u{2714}".bright_green(),
+        records.len(),
+        file.display()
+    );
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::path::Path;
+
+    use age::armor::ArmoredReader;
+    use age::secrecy::SecretString;
+    use test_case::test_case;
+
+    use crate::export::{encrypt, get_format, serialize, Record};
+
+    fn records() -> Vec<Record> {
+        vec![Record {
+            id: 1,
+            name: "Production <DB>".to_owned(),
+            login: "admin".to_owned(),
+            password: "a&b\"c".to_owned(),
+            url: "db.example.org".to_owned(),
+            notes: "Primary, replicated".to_owned(),
+            category: "Databases".to_owned(),
+            client: "Acme".to_owned(),
+        }]
+    }
+
+    #[test_case("escrow.csv", "csv")]
+    #[test_case("escrow.csv.age", "csv")]
+    #[test_case("escrow.xml.age", "keepass")]
+    #[test_case("escrow.age", "json")]
+    #[test_case("-", "json")]
+    fn test_get_format(file: &str, expected: &str) {
+        assert_eq!(expected, get_format(Path::new(file), None));
+    }
+
+    #[test]
+    fn test_serialize_csv() {
+        let data = String::from_utf8(serialize(&records(), "csv").expect("Failed to serialize")).expect("UTF-8");
+
+        assert_eq!(
+            "id,name,login,password,url,notes,category,client\n\
+            1,Production <DB>,admin,\"a&b\"\"c\",db.example.org,\"Primary, replicated\",Databases,Acme\n",
+            data
+        );
+    }
+
+    #[test]
+    fn test_serialize_keepass() {
+        let data = String::from_utf8(serialize(&records(), "keepass").expect("Failed to serialize")).expect("UTF-8");
+        let document = roxmltree::Document::parse(&data).expect("Export should be valid XML");
+
+        let password = document
+            .descendants()
+            .find(|node| node.has_tag_name("Key") && node.text() == Some("Password"))
+            .and_then(|node| node.next_sibling_element())
+            .and_then(|node| node.text());
+        assert_eq!(Some("a&b\"c"), password);
+        assert!(data.contains("<Name>Acme</Name>"));
+        assert!(data.contains("Production &lt;DB&gt;"));
+    }
+
+    #[test]
+    fn test_serialize_keepass_control_characters() {
+        let mut records = records();
+        records[0].notes = "line\u{1b}[31m\u{0}\ttab\r\nnext".to_owned();
+        let data = String::from_utf8(serialize(&records, "keepass").expect("Failed to serialize")).expect("UTF-8");
+        let document = roxmltree::Document::parse(&data).expect("Export should be valid XML");
+
+        let notes = document
+            .descendants()
+            .find(|node| node.has_tag_name("Key") && node.text() == Some("Notes"))
+            .and_then(|node| node.next_sibling_element())
+            .and_then(|node| node.text());
+        assert_eq!(Some("line[31m\ttab\nnext"), notes);
+
+        records[0].password = "pass\u{7}word".to_owned();
+        let error = serialize(&records, "keepass").expect_err("A password can't lose characters");
+        assert!(error.to_string().contains("1. Production <DB>"), "{error}");
+        assert!(serialize(&records, "csv").is_ok());
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let passphrase = SecretString::from("passphrase".to_owned());
+        let encrypted =
+            encrypt(b"secret", age::Encryptor::with_user_passphrase(passphrase.clone())).expect("Failed to encrypt");
+        assert!(String::from_utf8_lossy(&encrypted).starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+
+        let identity: &dyn age::Identity = &age::scrypt::Identity::new(passphrase);
+        let mut decrypted = String::new();
+        age::Decryptor::new(ArmoredReader::new(encrypted.as_slice()))
+            .expect("Invalid age file")
+            .decrypt(std::iter::once(identity))
+            .expect("Failed to decrypt")
+            .read_to_string(&mut decrypted)
+            .expect("Failed to read");
+        assert_eq!("secret", decrypted);
+    }
+}
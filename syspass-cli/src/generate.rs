@@ -0,0 +1,353 @@
+use std::error::Error;
+use std::io::Write;
+
+use clap::builder::RangedU64ValueParser;
+use clap::{arg, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::warn;
+use passwords::{analyzer, scorer};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::generate::passphrase::{entropy_strength, Passphrase, SEPARATOR};
+use crate::output::Format;
+use crate::prompt::password_strength;
+use crate::search::copy_to_clipboard;
+
+pub mod passphrase;
+
+pub const COMMAND_NAME: &str = "generate";
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const NUMBERS: &str = "0123456789";
+// Without '<', which the suggestions never contained
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;=>?@[\\]^_`{|}~";
+const SIMILAR: &str = "iIlL1|oO0`'\"";
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Policy {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub numbers: bool,
+    pub symbols: bool,
+    pub exclude: String,
+    pub exclude_similar: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            numbers: true,
+            symbols: true,
+            exclude: String::new(),
+            exclude_similar: true,
+        }
+    }
+}
+
+impl Policy {
+    fn classes(&self) -> Result<Vec<Vec<char>>, String> {
+        let excluded = |char: char| self.exclude.contains(char) || (self.exclude_similar && SIMILAR.contains(char));
+        let classes: Vec<Vec<char>> = [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.numbers, NUMBERS),
+            (self.symbols, SYMBOLS),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, class)| class.chars().filter(|char| !excluded(*char)).collect())
+        .collect();
+
+        if classes.is_empty() {
+            Err("The password policy has no character classes".to_owned())
+        } else if classes.iter().any(Vec::is_empty) {
+            Err("The password policy excludes every character of a class".to_owned())
+        } else if self.length < classes.len() {
+            Err(format!(
+                "A password of length {} can't contain all {} character classes",
+                self.length,
+                classes.len()
+            ))
+        } else {
+            Ok(classes)
+        }
+    }
+
+    // One character of every class, the rest from all of them
+    pub fn generate(&self) -> Result<String, String> {
+        let classes = self.classes()?;
+        let all: Vec<char> = classes.concat();
+        let mut rng = rand::thread_rng();
+
+        let mut password: Vec<char> = classes
+            .iter()
+            .filter_map(|class| class.choose(&mut rng))
+            .copied()
+            .collect();
+        while password.len() < self.length {
+            password.extend(all.choose(&mut rng));
+        }
+        password.shuffle(&mut rng);
+
+        Ok(password.into_iter().collect())
+    }
+}
+
+// The named policy, or the configured passwordPolicy. Policies that can't generate a password are an error
+pub fn get_policy<'a>(config: &'a Config, name: Option<&str>) -> Result<Option<&'a Policy>, String> {
+    name.or(config.password_policy.as_deref())
+        .map(|name| {
+            config
+                .password_policies
+                .get(name)
+                .ok_or_else(|| format!("Password policy {name} not found in config"))
+                .and_then(|policy| policy.classes().map(|_| policy))
+        })
+        .transpose()
+}
+
+#[derive(Serialize)]
+struct Generated {
+    password: String,
+    strength: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entropy: Option<f64>,
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Generate passwords")
+        .arg(
+            arg!(-P --policy <NAME> "Start from a password policy of the config file")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-l --length <LENGTH> "Password length")
+                .required(false)
+                .value_parser(clap::value_parser!(usize))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(arg!(--"no-lowercase" "Without lowercase letters"))
+        .arg(arg!(--"no-uppercase" "Without uppercase letters"))
+        .arg(arg!(--"no-numbers" "Without numbers"))
+        .arg(arg!(--"no-symbols" "Without symbols"))
+        .arg(
+            arg!(-x --exclude <CHARACTERS> "Characters the password must not contain")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(arg!(--similar "Allow similar characters like l, 1, O and 0"))
+        .arg(
+            arg!(-n --count <COUNT> "Number of passwords")
+                .required(false)
+                .default_value("1")
+                .value_parser(clap::value_parser!(usize))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(arg!(--clipboard "Copy the password to the clipboard instead of printing it"))
+        .arg(
+            arg!(--passphrase "Generate passphrases of words from the EFF large wordlist").conflicts_with_all([
+                "policy",
+                "length",
+                "no-lowercase",
+                "no-uppercase",
+                "no-numbers",
+                "no-symbols",
+                "exclude",
+                "similar",
+            ]),
+        )
+        .arg(
+            arg!(--words <WORDS> "Number of words in a passphrase")
+                .required(false)
+                .default_value("6")
+                .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+                .requires("passphrase")
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--separator <SEPARATOR> "Separator between the words of a passphrase")
+                .required(false)
+                .default_value(SEPARATOR)
+                .requires("passphrase")
+                .value_hint(ValueHint::Other),
+        )
+        .arg(arg!(--"no-capitalize" "Keep passphrase words lowercase").requires("passphrase"))
+        .arg(arg!(--"no-digit" "Don't add a digit to a passphrase").requires("passphrase"))
+}
+
+fn get_args_passphrase(matches: &ArgMatches) -> Passphrase {
+    Passphrase {
+        words: *matches.get_one::<usize>("words").expect("Words has a default"),
+        separator: matches
+            .get_one::<String>("separator")
+            .expect("Separator has a default")
+            .clone(),
+        capitalize: !matches.get_flag("no-capitalize"),
+        digit: !matches.get_flag("no-digit"),
+    }
+}
+
+fn generate_one(matches: &ArgMatches, policy: &Policy) -> Result<Generated, String> {
+    if matches.get_flag("passphrase") {
+        let passphrase = get_args_passphrase(matches);
+        let entropy = passphrase.entropy();
+        return Ok(Generated {
+            password: passphrase.generate(),
+            strength: entropy_strength(entropy),
+            score: None,
+            entropy: Some(entropy),
+        });
+    }
+
+    policy.generate().map(|password| {
+        let score = scorer::score(&analyzer::analyze(&password));
+        Generated {
+            password,
+            strength: password_strength(score),
+            score: Some(score),
+            entropy: None,
+        }
+    })
+}
+
+fn get_args_policy(matches: &ArgMatches, config: &Config) -> Result<Policy, String> {
+    let mut policy = get_policy(config, matches.get_one::<String>("policy").map(String::as_str))?
+        .cloned()
+        .unwrap_or_default();
+
+    if let Some(length) = matches.get_one::<usize>("length") {
+        policy.length = *length;
+    }
+    for (flag, enabled) in [
+        ("no-lowercase", &mut policy.lowercase),
+        ("no-uppercase", &mut policy.uppercase),
+        ("no-numbers", &mut policy.numbers),
+        ("no-symbols", &mut policy.symbols),
+    ] {
+        if matches.get_flag(flag) {
+            *enabled = false;
+        }
+    }
+    if let Some(exclude) = matches.get_one::<String>("exclude") {
+        policy.exclude.push_str(exclude);
+    }
+    if matches.get_flag("similar") {
+        policy.exclude_similar = false;
+    }
+
+    Ok(policy)
+}
+
+pub fn command(matches: &ArgMatches, config: &Config) -> Result<u8, Box<dyn Error>> {
+    let policy = get_args_policy(matches, config)?;
+    let count = *matches.get_one::<usize>("count").expect("Count has a default");
+    let clipboard = matches.get_flag("clipboard");
+    if clipboard && count != 1 {
+        Err("Only one password can be copied to the clipboard")?;
+    }
+
+    let passwords = (0..count)
+        .map(|_| generate_one(matches, &policy))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let format = Format::from(matches);
+    if clipboard {
+        copy_to_clipboard(&passwords[0].password, config)?;
+        warn!(
+            "{} Password copied to the clipboard ({})",
+            "\u{2714}".bright_green(),
+            passwords[0].strength.yellow()
+        );
+    } else if format.is_structured() {
+        format.print_list(&passwords)?;
+    } else {
+        let mut stdout = std::io::stdout().lock();
+        for generated in &passwords {
+            writeln!(stdout, "{}", generated.password)?;
+        }
+        stdout.flush()?;
+    }
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use crate::config::Config;
+    use crate::generate::{get_policy, Policy, SIMILAR};
+
+    #[test]
+    fn test_generate() {
+        let policy = Policy {
+            length: 12,
+            symbols: false,
+            exclude: "abcdef".to_owned(),
+            ..Default::default()
+        };
+
+        for _ in 0..50 {
+            let password = policy.generate().expect("Policy should be valid");
+
+            assert_eq!(12, password.chars().count());
+            assert!(password.chars().all(char::is_alphanumeric));
+            assert!(!password.contains(|char| "abcdef".contains(char) || SIMILAR.contains(char)));
+            assert!(password.contains(|char: char| char.is_ascii_lowercase()));
+            assert!(password.contains(|char: char| char.is_ascii_uppercase()));
+            assert!(password.contains(|char: char| char.is_ascii_digit()));
+        }
+    }
+
+    #[test_case(&Policy { lowercase: false, uppercase: false, numbers: false, symbols: false, ..Default::default() }; "no classes")]
+    #[test_case(&Policy { numbers: true, exclude: "23456789".to_owned(), ..Default::default() }; "empty class")]
+    #[test_case(&Policy { length: 3, ..Default::default() }; "too short")]
+    fn test_generate_invalid(policy: &Policy) {
+        assert!(policy.generate().is_err());
+    }
+
+    #[test]
+    fn test_get_policy() {
+        let short = Policy {
+            length: 8,
+            symbols: false,
+            ..Default::default()
+        };
+        let mut config = Config::default();
+        config.password_policies.insert("short".to_owned(), short.clone());
+
+        assert_eq!(None, get_policy(&config, None).expect("No policy is fine"));
+        assert_eq!(Some(&short), get_policy(&config, Some("short")).expect("Policy exists"));
+        assert!(get_policy(&config, Some("missing")).is_err());
+
+        config.password_policy = Some("short".to_owned());
+        assert_eq!(Some(&short), get_policy(&config, None).expect("Policy exists"));
+    }
+
+    #[test]
+    fn test_deserialize_policy() {
+        let policy: Policy = serde_json::from_str(r#"{"length": 12, "symbols": false}"#).expect("Valid policy");
+
+        assert_eq!(
+            Policy {
+                length: 12,
+                symbols: false,
+                ..Default::default()
+            },
+            policy
+        );
+    }
+}
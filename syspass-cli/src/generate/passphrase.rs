@@ -0,0 +1,148 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// The EFF large wordlist, one "dice rolls<TAB>word" line for each of the 6^5 rolls of five dice
+const WORDLIST: &str = include_str!("eff_large_wordlist.txt");
+// Some words of the list contain a hyphen, so they are joined with a character that never appears in it
+pub const SEPARATOR: &str = ".";
+
+pub struct Passphrase {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    pub digit: bool,
+}
+
+impl Default for Passphrase {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: SEPARATOR.to_owned(),
+            capitalize: true,
+            digit: true,
+        }
+    }
+}
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST
+        .lines()
+        .filter_map(|line| line.split_once('\t').map(|(_, word)| word))
+        .collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+impl Passphrase {
+    pub fn generate(&self) -> String {
+        self.words().join(&self.separator)
+    }
+
+    // The digit is appended to one of the words
+    fn words(&self) -> Vec<String> {
+        let wordlist = wordlist();
+        let mut rng = rand::thread_rng();
+
+        let mut words: Vec<String> = (0..self.words)
+            .filter_map(|_| wordlist.choose(&mut rng))
+            .map(|word| {
+                if self.capitalize {
+                    capitalize(word)
+                } else {
+                    (*word).to_owned()
+                }
+            })
+            .collect();
+        if self.digit {
+            if let Some(word) = words.choose_mut(&mut rng) {
+                word.push(char::from(b'0' + rng.gen_range(0..10)));
+            }
+        }
+
+        words
+    }
+
+    // Bits of entropy, capitalizing every word adds none
+    #[allow(clippy::cast_precision_loss)]
+    pub fn entropy(&self) -> f64 {
+        let words = self.words as f64;
+        let digit = if self.digit && self.words > 0 {
+            (10.0 * words).log2()
+        } else {
+            0.0
+        };
+
+        words.mul_add((wordlist().len() as f64).log2(), digit)
+    }
+}
+
+pub fn entropy_strength(entropy: f64) -> String {
+    format!("{entropy:.0} bits")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::passphrase::{capitalize, wordlist, Passphrase, SEPARATOR};
+
+    #[test]
+    fn test_wordlist() {
+        let wordlist = wordlist();
+
+        assert_eq!(7776, wordlist.len());
+        assert_eq!(Some(&"abacus"), wordlist.first());
+        assert_eq!(Some(&"zoom"), wordlist.last());
+        assert!(wordlist.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(wordlist.iter().all(|word| !word.contains(SEPARATOR)));
+    }
+
+    #[test]
+    fn test_generate() {
+        let words = Passphrase::default().words();
+
+        assert_eq!(6, words.len());
+        assert!(words
+            .iter()
+            .all(|word| word.starts_with(|char: char| char.is_ascii_uppercase())));
+        assert_eq!(1, words.concat().chars().filter(char::is_ascii_digit).count());
+        assert_eq!(5, Passphrase::default().generate().matches(SEPARATOR).count());
+    }
+
+    #[test]
+    fn test_generate_plain() {
+        let words = Passphrase {
+            words: 4,
+            separator: " ".to_owned(),
+            capitalize: false,
+            digit: false,
+        }
+        .words();
+        let wordlist = wordlist();
+
+        assert!(words.iter().all(|word| wordlist.contains(&word.as_str())));
+        assert_eq!(4, words.len());
+    }
+
+    #[test]
+    fn test_entropy() {
+        let plain = Passphrase {
+            digit: false,
+            ..Default::default()
+        };
+
+        assert!((plain.entropy() - 77.55).abs() < 0.01);
+        assert!((Passphrase::default().entropy() - 83.46).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_capitalize() {
+        assert_eq!("Abacus", capitalize("abacus"));
+        assert_eq!("T-shirt", capitalize("t-shirt"));
+        assert_eq!("", capitalize(""));
+    }
+}
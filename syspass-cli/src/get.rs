@@ -0,0 +1,137 @@
+use std::error::Error;
+use std::io::Write;
+
+use clap::{arg, Arg, ArgAction, ArgMatches, Command, ValueHint};
+
+use crate::api::account::Account;
+use crate::api::{self, Client};
+
+pub const COMMAND_NAME: &str = "get";
+
+const FIELDS: [&str; 5] = ["password", "login", "url", "notes", "name"];
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Print a single field of an account without any decoration")
+        .short_flag('g')
+        .arg(
+            arg!(<account> "Account id or exact account name")
+                .required(true)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-f --field <FIELD> "Field to print")
+                .required(false)
+                .default_value("password")
+                .value_parser(FIELDS),
+        )
+        .arg(
+            Arg::new("no-newline")
+                .short('n')
+                .long("no-newline")
+                .action(ArgAction::SetTrue)
+                .help("Do not print the trailing newline"),
+        )
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client) -> Result<u8, Box<dyn Error>> {
+    let search = matches.get_one::<String>("account").expect("Account is required");
+    let field = matches.get_one::<String>("field").map_or("password", String::as_str);
+
+    let account = find_account(api_client, search)?;
+    let value = match field {
+        "password" => api_client.get_password(&account)?.password,
+        "login" => account.login().to_owned(),
+        "url" => account.url().unwrap_or_default().to_owned(),
+        "notes" => account.notes().unwrap_or_default().to_owned(),
+        _ => account.name().to_owned(),
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(value.as_bytes())?;
+    if !matches.get_flag("no-newline") {
+        stdout.write_all(b"\n")?;
+    }
+    stdout.flush()?;
+
+    Ok(0)
+}
+
+fn find_account(api_client: &dyn Client, search: &str) -> Result<Account, api::Error> {
+    if let Ok(id) = search.parse::<u32>() {
+        return api_client.view_account(id);
+    }
+
+    // Every match is fetched so an exact name beyond the server's default page is still found
+    let search_string = vec![("text", search.to_owned()), ("count", api::ACCOUNT_LIMIT.to_owned())];
+
+    pick_account(&api_client.search_account(search_string, false)?, search)
+}
+
+fn pick_account(accounts: &[Account], search: &str) -> Result<Account, api::Error> {
+    let exact: Vec<&Account> = accounts.iter().filter(|account| account.name() == search).collect();
+
+    match (exact.len(), accounts.len()) {
+        (1, _) => Ok(exact[0].clone()),
+        (0, 1) => Ok(accounts[0].clone()),
+        (0, 0) => Err(api::Error::NotFound(format!("No account found for \"{search}\""))),
+        (0, count) | (count, _) => Err(api::Error::Ambiguous(format!(
+            "{count} accounts match \"{search}\", use an account id instead"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::account::Account;
+    use crate::get::pick_account;
+
+    fn account(id: u32, name: &str) -> Account {
+        Account::new(
+            Some(id),
+            name.to_owned(),
+            "login".to_owned(),
+            None,
+            None,
+            1,
+            1,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_pick_account_exact_match() {
+        let picked = pick_account(&[account(1, "github"), account(2, "github enterprise")], "github")
+            .expect("Exact match should be picked");
+
+        assert_eq!("github", picked.name());
+    }
+
+    #[test]
+    fn test_pick_account_single_result() {
+        let picked = pick_account(&[account(2, "github enterprise")], "github").expect("Only result should be picked");
+
+        assert_eq!("github enterprise", picked.name());
+    }
+
+    #[test]
+    fn test_pick_account_ambiguous() {
+        let result = pick_account(&[account(1, "github"), account(2, "github")], "github");
+
+        assert!(result.is_err_and(|error| error.exit_code() == 9
+            && error.to_string() == "2 accounts match \"github\", use an account id instead"));
+
+        let result = pick_account(&[account(1, "github one"), account(2, "github two")], "github");
+
+        assert!(
+            result.is_err_and(|error| error.to_string() == "2 accounts match \"github\", use an account id instead")
+        );
+    }
+
+    #[test]
+    fn test_pick_account_not_found() {
+        assert!(pick_account(&[], "github")
+            .is_err_and(|error| error.exit_code() == 8 && error.to_string() == "No account found for \"github\""));
+    }
+}
@@ -1,30 +1,50 @@
-use std::process;
-
+use chrono::{NaiveDate, NaiveTime};
 use clap::ArgMatches;
-use log::warn;
 
-pub fn get_numeric_input<F>(field: &str, matches: &ArgMatches, new: bool, callback: Option<F>, quiet: bool) -> u32
+use crate::api;
+
+pub fn get_numeric_input<F>(
+    field: &str,
+    matches: &ArgMatches,
+    new: bool,
+    callback: Option<F>,
+    quiet: bool,
+) -> Result<u32, api::Error>
 where
-    F: FnOnce() -> u32,
+    F: FnOnce() -> Result<u32, api::Error>,
 {
-    matches
-        .get_one::<u32>(field)
-        .map(std::borrow::ToOwned::to_owned)
-        .map_or_else(
-            || {
-                if new {
-                    0
-                } else if quiet {
-                    warn!("Could not ask for input");
-                    process::exit(1);
-                } else if let Some(callback) = callback {
-                    callback()
-                } else {
-                    0
-                }
-            },
-            |id| id,
-        )
+    match matches.get_one::<u32>(field) {
+        Some(value) => Ok(*value),
+        None if new => Ok(0),
+        None if quiet => Err(api::Error::Other(format!("Could not ask for {field}"))),
+        None => callback.map_or(Ok(0), |callback| callback()),
+    }
+}
+
+pub const DAY: i64 = 24 * 60 * 60;
+
+// Parses a number of days such as 30, 30d or 4w
+pub fn parse_days(input: &str) -> Result<u32, String> {
+    let input = input.trim();
+    let (number, factor) = input
+        .strip_suffix('w')
+        .map_or_else(|| (input.strip_suffix('d').unwrap_or(input), 1), |weeks| (weeks, 7));
+
+    number
+        .parse::<u32>()
+        .ok()
+        .and_then(|number| number.checked_mul(factor))
+        .ok_or_else(|| format!("Expected a number of days like 30d or 4w, got {input}"))
+}
+
+pub fn parse_date(input: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Expected a date like 2030-01-31, got {input}"))
+}
+
+// Expiration dates last until the end of the day
+pub const fn end_of_day(date: NaiveDate) -> i64 {
+    date.and_time(NaiveTime::MIN).and_utc().timestamp() + DAY - 1
 }
 
 #[cfg(test)]
@@ -32,7 +52,8 @@ mod tests {
     use clap::{Arg, Command};
     use test_case::test_case;
 
-    use crate::helper::get_numeric_input;
+    use crate::api;
+    use crate::helper::{end_of_day, get_numeric_input, parse_date, parse_days};
 
     #[test_case("42", false, 42; "with id")]
     #[test_case("", false, 0; "without id")]
@@ -42,11 +63,7 @@ mod tests {
     fn test_get_numeric_input(id: &str, new: bool, result: u32) {
         let command = Command::new("test").arg(Arg::new("id").long("id").value_parser(clap::value_parser!(u32)));
 
-        let callback: Option<fn() -> u32> = if result == 1337 {
-            Some(|| 1337)
-        } else {
-            None::<fn() -> u32>
-        };
+        let callback: Option<fn() -> Result<u32, api::Error>> = if result == 1337 { Some(|| Ok(1337)) } else { None };
 
         let input = if id.is_empty() {
             vec!["test"]
@@ -55,8 +72,43 @@ mod tests {
         };
 
         assert_eq!(
-            get_numeric_input("id", &command.get_matches_from(input), new, callback, false),
+            get_numeric_input("id", &command.get_matches_from(input), new, callback, false)
+                .expect("Input should be found"),
             result
         );
     }
+
+    #[test]
+    fn test_get_numeric_input_quiet() {
+        let command = Command::new("test").arg(Arg::new("id").long("id").value_parser(clap::value_parser!(u32)));
+
+        assert!(get_numeric_input(
+            "id",
+            &command.get_matches_from(vec!["test"]),
+            false,
+            Some(|| Ok(1337)),
+            true
+        )
+        .is_err());
+    }
+
+    #[test_case("30", Some(30))]
+    #[test_case("30d", Some(30))]
+    #[test_case("4w", Some(28))]
+    #[test_case("0d", Some(0))]
+    #[test_case("d", None)]
+    #[test_case("30m", None)]
+    #[test_case("-1d", None)]
+    fn test_parse_days(input: &str, expected: Option<u32>) {
+        assert_eq!(expected, parse_days(input).ok());
+    }
+
+    #[test_case("2030-01-01", Some(1_893_542_399))]
+    #[test_case("2024-02-29", Some(1_709_251_199))]
+    #[test_case("2024-13-45", None)]
+    #[test_case("2023-02-29", None)]
+    #[test_case("tomorrow", None)]
+    fn test_parse_date(input: &str, expected: Option<i64>) {
+        assert_eq!(expected, parse_date(input).ok().map(end_of_day));
+    }
 }
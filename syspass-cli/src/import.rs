@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{arg, ArgAction, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::api::account::Account;
+use crate::api::category::Category;
+use crate::api::client::Client as SyspassClient;
+use crate::api::entity::Entity;
+use crate::api::{self, Client};
+use crate::cache;
+use crate::output::Format;
+
+mod bitwarden;
+mod csv_file;
+mod keepass;
+
+pub const COMMAND_NAME: &str = "import";
+
+const CSV: &str = "csv";
+const KEEPASS: &str = "keepass";
+const BITWARDEN: &str = "bitwarden";
+const DEFAULT_NAME: &str = "Imported";
+
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub login: String,
+    pub password: String,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+    pub category: Option<String>,
+    pub client: Option<String>,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Summary {
+    dry_run: bool,
+    imported: usize,
+    categories: usize,
+    clients: usize,
+    duplicates: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Import accounts from CSV, KeePass 2 XML or Bitwarden JSON")
+        .arg(
+            arg!(<FILE> "File to import")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_hint(ValueHint::FilePath),
+        )
+        .arg(
+            arg!(-f --format <FORMAT> "File format, guessed from the extension by default")
+                .required(false)
+                .value_parser([CSV, KEEPASS, BITWARDEN]),
+        )
+        .arg(
+            arg!(-m --map <FIELD_COLUMN> "Read a field from a CSV column, e.g. --map login=Username")
+                .required(false)
+                .action(ArgAction::Append)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-a --category <NAME> "Category for entries without a group or folder")
+                .required(false)
+                .default_value(DEFAULT_NAME)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-i --client <NAME> "Client for entries without one")
+                .required(false)
+                .default_value(DEFAULT_NAME)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(arg!(--"dry-run" "Only report what would be imported"))
+}
+
+fn get_format<'a>(file: &Path, format: Option<&'a str>) -> Result<&'a str, String> {
+    format.map_or_else(
+        || match file.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Ok(CSV),
+            Some("xml") => Ok(KEEPASS),
+            Some("json") => Ok(BITWARDEN),
+            _ => Err(format!("Unknown file format of {}, use --format", file.display())),
+        },
+        Ok,
+    )
+}
+
+fn parse(file: &Path, matches: &ArgMatches) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let data = fs::read_to_string(file).map_err(|error| format!("Could not read {}: {error}", file.display()))?;
+    let mappings = matches
+        .get_many::<String>("map")
+        .map(|mappings| {
+            mappings
+                .map(|mapping| {
+                    mapping
+                        .split_once('=')
+                        .map(|(field, column)| (field.to_owned(), column.to_owned()))
+                        .ok_or_else(|| format!("Expected FIELD=COLUMN for --map, got {mapping}"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    match get_format(file, matches.get_one::<String>("format").map(String::as_str))? {
+        CSV => csv_file::parse(&data, &mappings),
+        KEEPASS => keepass::parse(&data),
+        _ => bitwarden::parse(&data),
+    }
+}
+
+fn get_key(name: &str, login: &str, url: Option<&str>) -> (String, String, String) {
+    (
+        name.trim().to_lowercase(),
+        login.trim().to_lowercase(),
+        url.unwrap_or_default().trim().to_lowercase(),
+    )
+}
+
+// Ids of existing categories or clients by lowercase name, missing ones are created on first use
+struct Names {
+    ids: HashMap<String, u32>,
+    created: usize,
+}
+
+impl Names {
+    fn new<'a>(items: impl Iterator<Item = (&'a str, Option<&'a u32>)>) -> Self {
+        Self {
+            ids: items
+                .filter_map(|(name, id)| id.map(|id| (name.to_lowercase(), *id)))
+                .collect(),
+            created: 0,
+        }
+    }
+
+    fn get_or_create<F>(&mut self, name: &str, dry_run: bool, create: F) -> Result<u32, api::Error>
+    where
+        F: FnOnce(&str) -> Result<Option<u32>, api::Error>,
+    {
+        if let Some(id) = self.ids.get(&name.to_lowercase()) {
+            return Ok(*id);
+        }
+
+        let id = if dry_run { 0 } else { create(name)?.unwrap_or(0) };
+        self.ids.insert(name.to_lowercase(), id);
+        self.created += 1;
+
+        Ok(id)
+    }
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client) -> Result<u8, Box<dyn Error>> {
+    let file = matches.get_one::<PathBuf>("FILE").expect("File is required");
+    let default_category = matches.get_one::<String>("category").expect("Category has a default");
+    let default_client = matches.get_one::<String>("client").expect("Client has a default");
+    let entries = parse(file, matches)?;
+
+    let mut summary = Summary {
+        dry_run: matches.get_flag("dry-run"),
+        ..Default::default()
+    };
+    let mut existing: HashSet<_> = api_client
+        .search_account(vec![("count", api::ACCOUNT_LIMIT.to_owned())], false)?
+        .iter()
+        .map(|account| get_key(account.name(), account.login(), account.url()))
+        .collect();
+    let mut categories = Names::new(
+        api_client
+            .get_categories()?
+            .iter()
+            .map(|category| (category.name(), category.id())),
+    );
+    let mut clients = Names::new(
+        api_client
+            .get_clients()?
+            .iter()
+            .map(|client| (client.name(), client.id())),
+    );
+
+    for entry in entries {
+        if entry.name.trim().is_empty() || entry.password.is_empty() {
+            warn!("Skipping {}: name and password are required", entry.name);
+            summary.skipped += 1;
+            continue;
+        }
+        // Only a saved entry counts, so a later copy of one that failed is still imported
+        let key = get_key(&entry.name, &entry.login, entry.url.as_deref());
+        if existing.contains(&key) {
+            info!("Skipping {}: already exists", entry.name);
+            summary.duplicates += 1;
+            continue;
+        }
+
+        let dry_run = summary.dry_run;
+        let result = categories
+            .get_or_create(entry.category.as_deref().unwrap_or(default_category), dry_run, |name| {
+                Ok(api_client
+                    .save_category(&Category::new(None, name.to_owned(), None))?
+                    .id()
+                    .copied())
+            })
+            .and_then(|category| {
+                clients
+                    .get_or_create(entry.client.as_deref().unwrap_or(default_client), dry_run, |name| {
+                        Ok(api_client
+                            .save_client(&SyspassClient::new(None, name.to_owned(), None, 0))?
+                            .id()
+                            .copied())
+                    })
+                    .map(|client| (category, client))
+            });
+
+        match result.and_then(|(category, client)| save(api_client, entry, category, client, dry_run)) {
+            Ok(()) => {
+                existing.insert(key);
+                summary.imported += 1;
+            }
+            Err(error) => {
+                warn!("{} {error}", "\u{2716}".bright_red());
+                summary.failed += 1;
+            }
+        }
+    }
+
+    summary.categories = categories.created;
+    summary.clients = clients.created;
+    if !summary.dry_run && summary.imported + summary.categories + summary.clients > 0 {
+        cache::invalidate(api_client.get_config());
+    }
+    print_summary(&summary, Format::from(matches))?;
+
+    Ok(u8::from(summary.failed > 0))
+}
+
+fn save(api_client: &dyn Client, entry: Entry, category: u32, client: u32, dry_run: bool) -> Result<(), api::Error> {
+    if dry_run {
+        info!("Would import {}", entry.name);
+        return Ok(());
+    }
+
+    let account = Account::new(
+        None,
+        entry.name,
+        entry.login,
+        entry.url,
+        entry.notes,
+        category,
+        client,
+        Some(entry.password),
+        None,
+    );
+    let saved = api_client
+        .save_account(&account)
+        .map_err(|error| api::Error::Other(format!("Could not import {}: {error}", account.name())))?;
+    info!("Imported {} ({})", saved.name(), saved.id().unwrap_or(&0));
+
+    Ok(())
+}
+
+fn print_summary(summary: &Summary, format: Format) -> Result<(), Box<dyn Error>> {
+    if format.is_structured() {
+        return format.print_one(summary);
+    }
+
+    warn!(
+        "{} {} {} account(s), {} {} categories and {} client(s). Skipped {} duplicate(s) and {} invalid entries{}",
+        "\u{2714}".bright_green(),
+        if summary.dry_run { "Would import" } else { "Imported" },
+        summary.imported,
+        if summary.dry_run { "would create" } else { "created" },
+        summary.categories,
+        summary.clients,
+        summary.duplicates,
+        summary.skipped,
+        if summary.failed > 0 {
+            format!(", {} failed", summary.failed)
+        } else {
+            String::new()
+        }
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use test_case::test_case;
+
+    use crate::import::{get_format, get_key, Names};
+
+    #[test_case("accounts.csv", None, Some("csv"))]
+    #[test_case("export.xml", None, Some("keepass"))]
+    #[test_case("export.json", None, Some("bitwarden"))]
+    #[test_case("export.txt", Some("csv"), Some("csv"))]
+    #[test_case("export.txt", None, None)]
+    fn test_get_format(file: &str, format: Option<&str>, expected: Option<&str>) {
+        assert_eq!(expected, get_format(Path::new(file), format).ok());
+    }
+
+    #[test]
+    fn test_get_key() {
+        assert_eq!(
+            get_key("Mail ", "Admin", Some("https://mail.example.org")),
+            get_key("mail", "admin", Some("https://MAIL.example.org"))
+        );
+        assert_ne!(get_key("mail", "admin", None), get_key("mail", "root", None));
+    }
+
+    #[test]
+    fn test_names() {
+        let id = 4;
+        let mut names = Names::new([("Databases", Some(&id))].into_iter());
+
+        assert_eq!(Some(4), names.get_or_create("databases", false, |_| Ok(None)).ok());
+        assert_eq!(Some(9), names.get_or_create("Web", false, |_| Ok(Some(9))).ok());
+        assert_eq!(Some(9), names.get_or_create("web", false, |_| Ok(Some(10))).ok());
+        assert_eq!(Some(0), names.get_or_create("Mail", true, |_| Ok(Some(11))).ok());
+        assert_eq!(2, names.created);
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use serde::Deserialize;
+
+use crate::import::Entry;
+
+const LOGIN: u8 = 1;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Named>,
+    #[serde(default)]
+    collections: Vec<Named>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Named {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "type")]
+    kind: u8,
+    name: String,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    collection_ids: Option<Vec<String>>,
+    login: Option<Login>,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    uris: Option<Vec<Uri>>,
+}
+
+#[derive(Deserialize)]
+struct Uri {
+    uri: Option<String>,
+}
+
+fn get_names(list: &[Named]) -> HashMap<&str, &str> {
+    list.iter()
+        .map(|named| (named.id.as_str(), named.name.as_str()))
+        .collect()
+}
+
+// Folders become categories and the first collection of an organization export the client.
+// Cards, identities and notes have no password and are reported as skipped
+pub fn parse(data: &str) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let export: Export = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(data))
+        .map_err(|error| format!("Not a Bitwarden JSON export: {error}"))?;
+    if export.encrypted {
+        return Err("Encrypted Bitwarden exports are not supported, export as unencrypted JSON".into());
+    }
+
+    let folders = get_names(&export.folders);
+    let collections = get_names(&export.collections);
+
+    Ok(export
+        .items
+        .into_iter()
+        .map(|item| {
+            let login = item.login.filter(|_| item.kind == LOGIN);
+            Entry {
+                name: item.name.trim().to_owned(),
+                login: login
+                    .as_ref()
+                    .and_then(|login| login.username.as_deref())
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned(),
+                password: login
+                    .as_ref()
+                    .and_then(|login| login.password.clone())
+                    .unwrap_or_default(),
+                url: login
+                    .as_ref()
+                    .and_then(|login| login.uris.as_ref())
+                    .and_then(|uris| uris.iter().find_map(|uri| uri.uri.clone())),
+                notes: item.notes.filter(|notes| !notes.trim().is_empty()),
+                category: item
+                    .folder_id
+                    .and_then(|id| folders.get(id.as_str()).map(|name| (*name).to_owned())),
+                client: item
+                    .collection_ids
+                    .and_then(|ids| ids.iter().find_map(|id| collections.get(id.as_str())).copied())
+                    .map(ToOwned::to_owned),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::import::bitwarden::parse;
+    use crate::import::Entry;
+
+    #[test]
+    fn test_parse() {
+        let data = fs::read_to_string("tests/import/bitwarden.json").expect("Failed to read fixture");
+        let entries = parse(&data).expect("Failed to parse");
+
+        assert_eq!(3, entries.len());
+        assert_eq!(
+            Entry {
+                name: "Production DB".to_owned(),
+                login: "admin".to_owned(),
+                password: "db-secret".to_owned(),
+                url: Some("db.example.org".to_owned()),
+                notes: Some("Primary".to_owned()),
+                category: Some("Databases".to_owned()),
+                client: Some("Acme".to_owned()),
+            },
+            entries[0]
+        );
+        assert_eq!(None, entries[1].category);
+        assert_eq!("", entries[2].password);
+    }
+
+    #[test]
+    fn test_parse_encrypted() {
+        assert!(parse("{\"encrypted\": true, \"items\": []}").is_err());
+        assert!(parse("{\"items\": {}}").is_err());
+    }
+}
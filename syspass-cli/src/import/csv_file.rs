@@ -0,0 +1,138 @@
+use std::error::Error;
+
+use csv::{ReaderBuilder, StringRecord};
+
+use crate::import::Entry;
+
+const FIELDS: [&str; 7] = ["name", "login", "password", "url", "notes", "category", "client"];
+
+// Header names used by KeePass, Bitwarden and most browser exports
+fn get_aliases(field: &str) -> &'static [&'static str] {
+    match field {
+        "name" => &["name", "title", "account"],
+        "login" => &["login", "username", "user name", "login_username", "user"],
+        "password" => &["password", "pass", "login_password"],
+        "url" => &["url", "uri", "login_uri", "website", "web site"],
+        "notes" => &["notes", "note", "comments", "extra"],
+        "category" => &["category", "group", "folder"],
+        "client" => &["client", "customer"],
+        _ => &[],
+    }
+}
+
+fn find_column(headers: &StringRecord, field: &str, mappings: &[(String, String)]) -> Result<Option<usize>, String> {
+    let position = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+    };
+
+    match mappings.iter().find(|(mapped, _)| mapped == field) {
+        Some((_, column)) => position(column)
+            .map(Some)
+            .ok_or_else(|| format!("Column {column} not found in the CSV header")),
+        None => Ok(get_aliases(field).iter().find_map(|alias| position(alias))),
+    }
+}
+
+pub fn parse(data: &str, mappings: &[(String, String)]) -> Result<Vec<Entry>, Box<dyn Error>> {
+    if let Some((field, _)) = mappings.iter().find(|(field, _)| !FIELDS.contains(&field.as_str())) {
+        return Err(format!("Unknown field {field} for --map, expected one of {}", FIELDS.join(", ")).into());
+    }
+
+    let mut reader = ReaderBuilder::new().flexible(true).from_reader(data.as_bytes());
+    let headers = reader.headers()?.clone();
+    let columns = FIELDS
+        .iter()
+        .map(|field| find_column(&headers, field, mappings))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(field) = [0, 2]
+        .iter()
+        .find(|index| columns[**index].is_none())
+        .map(|index| FIELDS[*index])
+    {
+        return Err(format!("No {field} column found in the CSV header, use --map {field}=COLUMN").into());
+    }
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            let get = |index: usize| {
+                columns[index]
+                    .and_then(|column| record.get(column))
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(ToOwned::to_owned)
+            };
+
+            Ok(Entry {
+                name: get(0).unwrap_or_default(),
+                login: get(1).unwrap_or_default(),
+                password: columns[2]
+                    .and_then(|column| record.get(column))
+                    .unwrap_or_default()
+                    .to_owned(),
+                url: get(3),
+                notes: get(4),
+                category: get(5),
+                client: get(6),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::import::csv_file::parse;
+    use crate::import::Entry;
+
+    #[test]
+    fn test_parse() {
+        let data = fs::read_to_string("tests/import/accounts.csv").expect("Failed to read fixture");
+        let entries = parse(&data, &[]).expect("Failed to parse");
+
+        assert_eq!(3, entries.len());
+        assert_eq!(
+            Entry {
+                name: "Production DB".to_owned(),
+                login: "admin".to_owned(),
+                password: " spaced secret ".to_owned(),
+                url: Some("db.example.org".to_owned()),
+                notes: Some("Primary, replicated".to_owned()),
+                category: Some("Databases".to_owned()),
+                client: Some("Acme".to_owned()),
+            },
+            entries[0]
+        );
+        assert_eq!(None, entries[2].category);
+    }
+
+    #[test]
+    fn test_parse_with_mapping() {
+        let data = "Title,Username,Secret,Group\nMail,postmaster,secret,Web\n";
+
+        assert!(parse(data, &[]).is_err());
+
+        let entries = parse(
+            data,
+            &[
+                ("password".to_owned(), "secret".to_owned()),
+                ("client".to_owned(), "Group".to_owned()),
+            ],
+        )
+        .expect("Failed to parse");
+        assert_eq!("secret", entries[0].password);
+        assert_eq!(Some("Web".to_owned()), entries[0].category);
+        assert_eq!(Some("Web".to_owned()), entries[0].client);
+    }
+
+    #[test]
+    fn test_parse_with_invalid_mapping() {
+        assert!(parse("name,password\n", &[("tags".to_owned(), "name".to_owned())]).is_err());
+        assert!(parse("name,password\n", &[("login".to_owned(), "user".to_owned())]).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::error::Error;
+
+use roxmltree::{Document, Node};
+
+use crate::import::Entry;
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|child| child.text())
+}
+
+fn parse_entry(node: Node, path: &[&str]) -> Result<Entry, String> {
+    let mut entry = Entry::default();
+
+    for string in node.children().filter(|child| child.has_tag_name("String")) {
+        let value = child(string, "Value");
+        if value.and_then(|value| value.attribute("Protected")) == Some("True") {
+            return Err("Protected values are not supported, export the database as KeePass XML (2.x)".to_owned());
+        }
+
+        let value = value.and_then(|value| value.text()).unwrap_or_default().to_owned();
+        let non_empty = Some(value.clone()).filter(|value| !value.trim().is_empty());
+        match child_text(string, "Key").unwrap_or_default() {
+            "Title" => value.trim().clone_into(&mut entry.name),
+            "UserName" => value.trim().clone_into(&mut entry.login),
+            "Password" => entry.password = value,
+            "URL" => entry.url = non_empty,
+            "Notes" => entry.notes = non_empty,
+            _ => {}
+        }
+    }
+
+    // Nested groups below the root are read as client / ... / category
+    match path {
+        [] => {}
+        [category] => entry.category = Some((*category).to_owned()),
+        [client, .., category] => {
+            entry.client = Some((*client).to_owned());
+            entry.category = Some((*category).to_owned());
+        }
+    }
+
+    Ok(entry)
+}
+
+fn parse_group<'a>(
+    node: Node<'a, '_>,
+    path: &mut Vec<&'a str>,
+    recycle_bin: Option<&str>,
+    entries: &mut Vec<Entry>,
+) -> Result<(), String> {
+    for item in node.children() {
+        if item.has_tag_name("Entry") {
+            entries.push(parse_entry(item, path)?);
+        } else if item.has_tag_name("Group") {
+            if recycle_bin.is_some() && child_text(item, "UUID") == recycle_bin {
+                continue;
+            }
+
+            path.push(child_text(item, "Name").unwrap_or_default());
+            parse_group(item, path, recycle_bin, entries)?;
+            path.pop();
+        }
+    }
+
+    Ok(())
+}
+
+pub fn parse(data: &str) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let document = Document::parse(data)?;
+    let file = document.root_element();
+    if !file.has_tag_name("KeePassFile") {
+        return Err("Not a KeePass XML file".into());
+    }
+
+    let recycle_bin = child(file, "Meta")
+        .filter(|meta| child_text(*meta, "RecycleBinEnabled") == Some("True"))
+        .and_then(|meta| child_text(meta, "RecycleBinUUID"));
+    let root = child(file, "Root")
+        .and_then(|root| child(root, "Group"))
+        .ok_or("KeePass XML file has no root group")?;
+
+    let mut entries = vec![];
+    parse_group(root, &mut vec![], recycle_bin, &mut entries)?;
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::import::keepass::parse;
+    use crate::import::Entry;
+
+    #[test]
+    fn test_parse() {
+        let data = fs::read_to_string("tests/import/keepass.xml").expect("Failed to read fixture");
+        let entries = parse(&data).expect("Failed to parse");
+
+        assert_eq!(
+            vec!["Router", "Production DB", "Mail"],
+            entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Entry {
+                name: "Production DB".to_owned(),
+                login: "admin".to_owned(),
+                password: "db-secret".to_owned(),
+                url: Some("db.example.org".to_owned()),
+                notes: Some("Primary".to_owned()),
+                category: Some("Databases".to_owned()),
+                client: Some("Acme".to_owned()),
+            },
+            entries[1]
+        );
+        assert_eq!(None, entries[0].category);
+        assert_eq!(Some("Web".to_owned()), entries[2].category);
+        assert_eq!(None, entries[2].client);
+    }
+
+    #[test]
+    fn test_parse_protected() {
+        let data = "<KeePassFile><Root><Group><Name>Root</Name><Entry><String><Key>Password</Key>\
+            <Value Protected=\"True\">c2VjcmV0</Value></String></Entry></Group></Root></KeePassFile>";
+
+        assert!(parse(data).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("<html></html>").is_err());
+        assert!(parse("not xml").is_err());
+    }
+}
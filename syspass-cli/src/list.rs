@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use clap::builder::RangedU64ValueParser;
+use clap::{arg, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::warn;
+use term_table::row::Row;
+use term_table::table_cell::TableCell;
+use term_table::{Table, TableStyle};
+
+use crate::api::entity::Entity;
+use crate::api::{self, Client};
+use crate::output::Format;
+use crate::{CommandError, DEFAULT_TERMINAL_SIZE, TERMINAL_SIZE};
+
+pub const COMMAND_NAME: &str = "list";
+
+const ACCOUNTS: &str = "accounts";
+const CATEGORIES: &str = "categories";
+const CLIENTS: &str = "clients";
+const TAGS: &str = "tags";
+
+const SORT_ID: &str = "id";
+const SORT_NAME: &str = "name";
+
+fn list_command(name: &'static str, about: &'static str) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            arg!(-t --text <TEXT> "Only list entries containing the text")
+                .required(false)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--sort <FIELD> "Sort by field")
+                .required(false)
+                .default_value(SORT_ID)
+                .value_parser([SORT_ID, SORT_NAME]),
+        )
+        .arg(
+            arg!(--count <COUNT> "Maximum number of entries to list")
+                .required(false)
+                .value_parser(clap::value_parser!(usize))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--offset <OFFSET> "Number of entries to skip")
+                .required(false)
+                .value_parser(clap::value_parser!(usize))
+                .conflicts_with("page")
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--page <PAGE> "Page of --count entries to list, starting at 1")
+                .required(false)
+                .requires("count")
+                .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+                .value_hint(ValueHint::Other),
+        )
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("List entities")
+        .short_flag('l')
+        .visible_alias("ls")
+        .subcommand_required(true)
+        .subcommand(
+            list_command(ACCOUNTS, "List accounts")
+                .visible_alias("account")
+                .arg(
+                    arg!(-a --category <CATEGORYID> "Category id")
+                        .required(false)
+                        .value_parser(clap::value_parser!(u32))
+                        .value_hint(ValueHint::Other),
+                )
+                .arg(
+                    arg!(-i --client <CLIENTID> "Client id")
+                        .required(false)
+                        .value_parser(clap::value_parser!(u32))
+                        .value_hint(ValueHint::Other),
+                )
+                .arg(
+                    arg!(--tag <TAGID> "Tag id")
+                        .required(false)
+                        .value_parser(clap::value_parser!(u32))
+                        .value_hint(ValueHint::Other),
+                ),
+        )
+        .subcommand(list_command(CATEGORIES, "List categories").visible_alias("category"))
+        .subcommand(list_command(CLIENTS, "List clients").visible_alias("client"))
+        .subcommand(list_command(TAGS, "List tags").visible_alias("tag"))
+}
+
+struct ListArgs<'a> {
+    format: Format,
+    text: &'a str,
+    by_name: bool,
+    count: Option<usize>,
+    offset: usize,
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client) -> Result<u8, Box<dyn Error>> {
+    let (name, matches) = matches.subcommand().ok_or(CommandError::NotFound)?;
+    let count = matches.get_one::<usize>("count").copied();
+    let args = ListArgs {
+        format: Format::from(matches),
+        text: matches.get_one::<String>("text").map_or("", String::as_str),
+        by_name: matches.get_one::<String>("sort").is_some_and(|sort| sort == SORT_NAME),
+        count,
+        offset: matches.get_one::<usize>("offset").copied().unwrap_or_else(|| {
+            matches
+                .get_one::<usize>("page")
+                .map_or(0, |page| (page - 1) * count.unwrap_or_default())
+        }),
+    };
+
+    match name {
+        ACCOUNTS => list_accounts(matches, api_client, &args),
+        CATEGORIES => list_categories(api_client, &args),
+        CLIENTS => list_clients(api_client, &args),
+        TAGS => list_tags(api_client, &args),
+        _ => Err(CommandError::NotFound)?,
+    }
+}
+
+fn list_accounts(matches: &ArgMatches, api_client: &dyn Client, args: &ListArgs) -> Result<u8, Box<dyn Error>> {
+    let mut search = vec![("text", args.text.to_owned())];
+    for (field, key) in [("category", "categoryId"), ("client", "clientId"), ("tag", "tagsId")] {
+        if let Some(id) = matches.get_one::<u32>(field) {
+            search.push((key, id.to_string()));
+        }
+    }
+    // Sorting and paging happen here, so every match is fetched first
+    search.push(("count", api::ACCOUNT_LIMIT.to_owned()));
+
+    let mut accounts = api_client.search_account(search, false)?;
+    sort_entities(&mut accounts, args.by_name, |account| account.name());
+    paginate(&mut accounts, args.offset, args.count);
+
+    if args.format.is_structured() {
+        args.format.print_list(&accounts)?;
+    } else {
+        print_table(
+            &["Id", "Name", "Login", "Url", "Client"],
+            accounts
+                .iter()
+                .map(|account| {
+                    vec![
+                        account.id().unwrap_or(&0).to_string(),
+                        account.name().to_owned(),
+                        account.login().to_owned(),
+                        account.url().unwrap_or_default().to_owned(),
+                        account.client_name().unwrap_or_default().to_owned(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    Ok(0)
+}
+
+fn list_categories(api_client: &dyn Client, args: &ListArgs) -> Result<u8, Box<dyn Error>> {
+    let mut categories = api_client.get_categories()?;
+    categories.retain(|category| matches_text(args.text, &[category.name(), category.description().unwrap_or("")]));
+    sort_entities(&mut categories, args.by_name, |category| category.name());
+    paginate(&mut categories, args.offset, args.count);
+
+    if args.format.is_structured() {
+        args.format.print_list(&categories)?;
+    } else {
+        print_table(
+            &["Id", "Name", "Description"],
+            categories
+                .iter()
+                .map(|category| {
+                    vec![
+                        category.id().unwrap_or(&0).to_string(),
+                        category.name().to_owned(),
+                        category.description().unwrap_or_default().to_owned(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    Ok(0)
+}
+
+fn list_clients(api_client: &dyn Client, args: &ListArgs) -> Result<u8, Box<dyn Error>> {
+    let mut clients = api_client.get_clients()?;
+    clients.retain(|client| matches_text(args.text, &[client.name(), client.description().unwrap_or("")]));
+    sort_entities(&mut clients, args.by_name, |client| client.name());
+    paginate(&mut clients, args.offset, args.count);
+
+    if args.format.is_structured() {
+        args.format.print_list(&clients)?;
+    } else {
+        print_table(
+            &["Id", "Name", "Description", "Global"],
+            clients
+                .iter()
+                .map(|client| {
+                    vec![
+                        client.id().unwrap_or(&0).to_string(),
+                        client.name().to_owned(),
+                        client.description().unwrap_or_default().to_owned(),
+                        if *client.is_global() > 0 { "\u{2714}" } else { "" }.to_owned(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    Ok(0)
+}
+
+fn list_tags(api_client: &dyn Client, args: &ListArgs) -> Result<u8, Box<dyn Error>> {
+    let mut tags = api_client.get_tags()?;
+    tags.retain(|tag| matches_text(args.text, &[tag.name()]));
+    sort_entities(&mut tags, args.by_name, |tag| tag.name());
+    paginate(&mut tags, args.offset, args.count);
+
+    if args.format.is_structured() {
+        args.format.print_list(&tags)?;
+    } else {
+        print_table(
+            &["Id", "Name"],
+            tags.iter()
+                .map(|tag| vec![tag.id().unwrap_or(&0).to_string(), tag.name().to_owned()])
+                .collect(),
+        );
+    }
+
+    Ok(0)
+}
+
+fn matches_text(text: &str, fields: &[&str]) -> bool {
+    let text = text.to_lowercase();
+    text.is_empty() || fields.iter().any(|field| field.to_lowercase().contains(&text))
+}
+
+fn sort_entities<T: Entity, F>(list: &mut [T], by_name: bool, name: F)
+where
+    F: Fn(&T) -> &str,
+{
+    if by_name {
+        list.sort_by_key(|entity| name(entity).to_lowercase());
+    } else {
+        list.sort_by(|a, b| a.id().cmp(&b.id()));
+    }
+}
+
+fn paginate<T>(list: &mut Vec<T>, offset: usize, count: Option<usize>) {
+    list.drain(..offset.min(list.len()));
+    list.truncate(count.unwrap_or(list.len()));
+}
+
+pub fn print_table(header: &[&str], rows: Vec<Vec<String>>) {
+    warn!("{}", render_table(header, rows));
+}
+
+fn render_table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut table = Table::new();
+    let terminal_width = TERMINAL_SIZE.try_lock().map_or(DEFAULT_TERMINAL_SIZE.0, |size| size.0);
+    let column_width = terminal_width / header.len().max(1);
+
+    table.max_column_widths = (0..header.len())
+        .map(|column| (column, column_width))
+        .collect::<HashMap<_, _>>();
+    table.style = TableStyle::rounded();
+
+    table.add_row(Row::new(header.iter().map(|title| TableCell::new(title.green()))));
+
+    for row in rows {
+        table.add_row(Row::new(row.into_iter().map(TableCell::new)));
+    }
+
+    table.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use crate::api::category::Category;
+    use crate::list::{matches_text, paginate, render_table, sort_entities};
+
+    #[test_case("", true)]
+    #[test_case("prod", true)]
+    #[test_case("PROD", true; "uppercase")]
+    #[test_case("database", true)]
+    #[test_case("staging", false)]
+    fn test_matches_text(text: &str, expected: bool) {
+        assert_eq!(expected, matches_text(text, &["Production", "database servers"]));
+    }
+
+    #[test]
+    fn test_sort_entities() {
+        let mut categories = vec![
+            Category::new(Some(3), "b".to_owned(), None),
+            Category::new(Some(1), "c".to_owned(), None),
+            Category::new(Some(2), "A".to_owned(), None),
+        ];
+
+        sort_entities(&mut categories, true, Category::name);
+        assert_eq!(
+            vec!["A", "b", "c"],
+            categories.iter().map(Category::name).collect::<Vec<_>>()
+        );
+
+        sort_entities(&mut categories, false, Category::name);
+        assert_eq!(
+            vec!["c", "A", "b"],
+            categories.iter().map(Category::name).collect::<Vec<_>>()
+        );
+    }
+
+    #[test_case(0, None, &[1, 2, 3, 4, 5])]
+    #[test_case(0, Some(2), &[1, 2])]
+    #[test_case(2, Some(2), &[3, 4]; "second page")]
+    #[test_case(4, Some(2), &[5]; "last page")]
+    #[test_case(9, None, &[]; "past the end")]
+    fn test_paginate(offset: usize, count: Option<usize>, expected: &[u32]) {
+        let mut list = vec![1, 2, 3, 4, 5];
+        paginate(&mut list, offset, count);
+
+        assert_eq!(expected, list);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let output = render_table(
+            &["Id", "Name"],
+            vec![
+                vec!["1".to_owned(), "first".to_owned()],
+                vec!["2".to_owned(), "second".to_owned()],
+            ],
+        );
+
+        assert!(output.contains("first"));
+        assert!(output.contains("second"));
+        assert!(strip_ansi_escapes::strip_str(output).contains("Name"));
+    }
+}
@@ -8,10 +8,8 @@
     clippy::style,
     clippy::complexity,
     clippy::perf,
-    clippy::pedantic,
     clippy::unwrap_used,
     clippy::nursery,
-    clippy::style,
     deprecated_in_future,
     future_incompatible,
     nonstandard_style,
@@ -22,8 +20,10 @@
 
 use std::error::Error;
 use std::io;
+use std::path::PathBuf;
 use std::process::ExitCode;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
 use clap::{arg, crate_description, crate_name, crate_version, value_parser, ArgAction, Command, ValueHint};
@@ -34,20 +34,37 @@ use terminal_size::{terminal_size, Height, Width};
 
 use crate::api::{Api, Client};
 use crate::config::Config;
+use crate::output::Format;
 
+mod admin;
+mod agent;
 mod api;
+mod breach;
+mod cache;
 mod config;
+mod credential;
 mod edit;
+mod expiring;
+mod export;
+mod generate;
+mod get;
 mod helper;
+mod import;
+mod list;
+mod output;
 mod prompt;
+mod redact;
 mod remove;
+mod rotate;
 mod search;
+mod strength;
 mod update;
 
 struct SimpleLogger;
 
 const DEFAULT_TERMINAL_SIZE: (usize, usize) = (80, 25);
 const COMMAND_NOT_FOUND: &str = "Command not found";
+const MISSING_HOST: &str = "No host configured, run `syspass-cli config init` or set SYSPASS_HOST";
 
 impl log::Log for SimpleLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
@@ -56,7 +73,7 @@ impl log::Log for SimpleLogger {
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
-            if record.metadata().level() == Level::Error {
+            if record.metadata().level() == Level::Error || LOG_TO_STDERR.load(Ordering::Relaxed) {
                 eprintln!("{}", record.args());
             } else {
                 println!("{}", record.args());
@@ -69,6 +86,8 @@ impl log::Log for SimpleLogger {
 
 static LOGGER: SimpleLogger = SimpleLogger;
 static TERMINAL_SIZE: Mutex<(usize, usize)> = Mutex::new(DEFAULT_TERMINAL_SIZE);
+static LOG_TO_STDERR: AtomicBool = AtomicBool::new(false);
+static DEBUG_UNSAFE: AtomicBool = AtomicBool::new(false);
 
 #[allow(clippy::cognitive_complexity)]
 fn get_command() -> Command {
@@ -84,6 +103,39 @@ fn get_command() -> Command {
                 .display_order(100)
                 .value_hint(ValueHint::FilePath),
         )
+        .arg(
+            arg!(--profile <NAME> "Use a named profile from the config file")
+                .global(true)
+                .required(false)
+                .display_order(100)
+                .env("SYSPASS_PROFILE")
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--set <KEY_VALUE> "Override a config key, e.g. --set verifyHost=true")
+                .global(true)
+                .required(false)
+                .display_order(100)
+                .action(ArgAction::Append)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--record <DIR> "Save every request and response to DIR with secrets masked")
+                .global(true)
+                .required(false)
+                .display_order(100)
+                .conflicts_with(config::REPLAY)
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::DirPath),
+        )
+        .arg(
+            arg!(--replay <DIR> "Answer requests from a directory saved with --record instead of the server")
+                .global(true)
+                .required(false)
+                .display_order(100)
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::DirPath),
+        )
         .arg(
             arg!(-q --quiet "Do not output any message")
                 .global(true)
@@ -97,11 +149,24 @@ fn get_command() -> Command {
                 .display_order(100),
         )
         .arg(
-            arg!(-d --debug "Output debug information")
+            arg!(-d --debug "Output debug information, secrets are masked")
                 .global(true)
                 .required(false)
                 .display_order(100),
         )
+        .arg(
+            arg!(--"debug-unsafe" "Output debug information including tokens and passwords")
+                .global(true)
+                .required(false)
+                .display_order(100),
+        )
+        .arg(
+            arg!(--output <FORMAT> "Print results as structured records. Messages are written to stderr")
+                .global(true)
+                .required(false)
+                .display_order(100)
+                .value_parser(output::FORMATS),
+        )
         .arg(
             arg!(--completions "Output debug information")
                 .action(ArgAction::Set)
@@ -109,9 +174,21 @@ fn get_command() -> Command {
                 .value_parser(value_parser!(Shell)),
         )
         .subcommand(search::command_helper())
+        .subcommand(get::command_helper())
         .subcommand(edit::command_helper_edit())
         .subcommand(remove::command_helper())
         .subcommand(edit::command_helper_new())
+        .subcommand(list::command_helper())
+        .subcommand(expiring::command_helper())
+        .subcommand(rotate::command_helper())
+        .subcommand(generate::command_helper())
+        .subcommand(breach::command_helper())
+        .subcommand(cache::command_helper())
+        .subcommand(import::command_helper())
+        .subcommand(export::command_helper())
+        .subcommand(admin::command_helper())
+        .subcommand(config::command_helper())
+        .subcommand(agent::command_helper())
         .subcommand(update::command_helper())
 }
 
@@ -128,17 +205,11 @@ fn main() -> ExitCode {
         return ExitCode::from(0);
     }
 
-    let config = Config::from(&matches);
-    let api_version = config.api_version.as_ref().map_or("", |version| version);
-    let api_client_box: Box<dyn Client> = Api::from_str(api_version)
-        .unwrap_or_else(|()| panic!("No such API is supported ({})", &api_version))
-        .get(config);
-
-    let api_client = api_client_box.as_ref();
-
     let quiet = matches.get_flag("quiet");
 
-    let log_level = if matches.get_flag("debug") {
+    DEBUG_UNSAFE.store(matches.get_flag("debug-unsafe"), Ordering::Relaxed);
+
+    let log_level = if matches.get_flag("debug") || matches.get_flag("debug-unsafe") {
         LevelFilter::Debug
     } else if matches.get_flag("verbose") {
         LevelFilter::Info
@@ -148,6 +219,8 @@ fn main() -> ExitCode {
         LevelFilter::Warn
     };
 
+    LOG_TO_STDERR.store(Format::from(&matches).is_structured(), Ordering::Relaxed);
+
     log::set_logger(&LOGGER)
         .map(|()| log::set_max_level(log_level))
         .expect("Failed to set logger");
@@ -155,21 +228,72 @@ fn main() -> ExitCode {
     *TERMINAL_SIZE.lock().expect("Fail") =
         terminal_size().map_or(DEFAULT_TERMINAL_SIZE, |(Width(w), Height(h))| (w as usize, h as usize));
 
-    match match matches.subcommand() {
+    if let Some((config::COMMAND_NAME, matches)) = matches.subcommand() {
+        return get_exit_code(config::command(matches));
+    }
+
+    let config = match Config::try_from(&matches) {
+        Ok(config) => config,
+        Err(error) => return get_exit_code(Err(Box::new(error))),
+    };
+    if config.host.is_empty()
+        && config.replay.is_none()
+        && !matches!(
+            matches.subcommand_name(),
+            Some(agent::COMMAND_NAME | update::COMMAND_NAME | generate::COMMAND_NAME | breach::COMMAND_NAME)
+        )
+    {
+        return get_exit_code(Err(Box::new(CommandError::MissingHost)));
+    }
+
+    let api_version = config.api_version.as_ref().map_or("", |version| version);
+    let Ok(api) = Api::from_str(api_version) else {
+        let error = config::LoadError(format!("No such API is supported ({api_version})"));
+        return get_exit_code(Err(Box::new(error)));
+    };
+    let api_client_box: Box<dyn Client> = api.get(config);
+
+    let api_client = api_client_box.as_ref();
+
+    get_exit_code(match matches.subcommand() {
         Some((search::COMMAND_NAME, matches)) => search::command(matches, api_client, quiet),
+        Some((get::COMMAND_NAME, matches)) => get::command(matches, api_client),
         Some((edit::COMMAND_NAME_EDIT, matches)) => edit::command_edit(matches, api_client, quiet),
         Some((remove::COMMAND_NAME, matches)) => remove::command(matches, api_client, quiet),
         Some((edit::COMMAND_NAME_NEW, matches)) => edit::command_new(matches, api_client, quiet),
+        Some((list::COMMAND_NAME, matches)) => list::command(matches, api_client),
+        Some((expiring::COMMAND_NAME, matches)) => expiring::command(matches, api_client),
+        Some((rotate::COMMAND_NAME, matches)) => rotate::command(matches, api_client),
+        Some((generate::COMMAND_NAME, matches)) => generate::command(matches, api_client.get_config()),
+        Some((breach::COMMAND_NAME, matches)) => breach::command(matches, api_client, quiet),
+        Some((cache::COMMAND_NAME, matches)) => cache::command(matches, api_client),
+        Some((import::COMMAND_NAME, matches)) => import::command(matches, api_client),
+        Some((export::COMMAND_NAME, matches)) => export::command(matches, api_client, quiet),
+        Some((admin::COMMAND_NAME, matches)) => admin::command(matches, api_client, quiet),
+        Some((agent::COMMAND_NAME, matches)) => agent::command(matches),
         Some((update::COMMAND_NAME, matches)) => update::command(matches),
         _ => {
             let error: Box<dyn Error> = Box::new(CommandError::NotFound);
             Err(error)
         }
-    } {
+    })
+}
+
+fn get_exit_code(result: Result<u8, Box<dyn Error>>) -> ExitCode {
+    match result {
         Ok(code) => ExitCode::from(code),
         Err(e) => {
             error!("{} {}", "\u{2716}".bright_red(), e);
-            ExitCode::from(1)
+            ExitCode::from(e.downcast_ref::<api::Error>().map_or_else(
+                || {
+                    if e.is::<config::LoadError>() {
+                        config::LoadError::EXIT_CODE
+                    } else {
+                        1
+                    }
+                },
+                api::Error::exit_code,
+            ))
         }
     }
 }
@@ -177,12 +301,14 @@ fn main() -> ExitCode {
 #[derive(Debug)]
 enum CommandError {
     NotFound,
+    MissingHost,
 }
 
 impl std::fmt::Display for CommandError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Self::NotFound => write!(f, "{COMMAND_NOT_FOUND}"),
+            Self::MissingHost => write!(f, "{MISSING_HOST}"),
         }
     }
 }
@@ -0,0 +1,165 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use clap::ArgMatches;
+use serde::Serialize;
+use serde_json::Value;
+
+pub const OUTPUT: &str = "output";
+pub const FORMATS: [&str; 4] = ["human", "json", "yaml", "tsv"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Human,
+    Json,
+    Yaml,
+    Tsv,
+}
+
+impl Format {
+    pub fn is_structured(self) -> bool {
+        self != Self::Human
+    }
+
+    pub fn print_one<T: Serialize>(self, record: &T) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Human => {}
+            Self::Json => println!("{}", serde_json::to_string_pretty(record)?),
+            Self::Yaml => print!("{}", serde_yaml::to_string(record)?),
+            Self::Tsv => print!("{}", to_tsv(&[serde_json::to_value(record)?])),
+        }
+
+        Ok(())
+    }
+
+    pub fn print_list<T: Serialize>(self, records: &[T]) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Human => {}
+            Self::Json => println!("{}", serde_json::to_string_pretty(records)?),
+            Self::Yaml => print!("{}", serde_yaml::to_string(records)?),
+            Self::Tsv => print!(
+                "{}",
+                to_tsv(
+                    &records
+                        .iter()
+                        .map(serde_json::to_value)
+                        .collect::<Result<Vec<Value>, serde_json::Error>>()?
+                )
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&ArgMatches> for Format {
+    fn from(matches: &ArgMatches) -> Self {
+        matches
+            .try_get_one::<String>(OUTPUT)
+            .ok()
+            .flatten()
+            .and_then(|format| Self::from_str(format).ok())
+            .unwrap_or(Self::Human)
+    }
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "human" | "" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(()),
+        }
+    }
+}
+
+// Optional fields are left out of some records, so the header is every key seen in the order it first appears
+fn to_tsv(records: &[Value]) -> String {
+    let mut header: Vec<&String> = Vec::new();
+    for key in records
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|record| record.keys())
+    {
+        if !header.contains(&key) {
+            header.push(key);
+        }
+    }
+    if header.is_empty() {
+        return String::new();
+    }
+
+    let mut lines = vec![header.iter().map(|key| key.as_str()).collect::<Vec<_>>().join("\t")];
+
+    for record in records {
+        lines.push(
+            header
+                .iter()
+                .map(|key| match record.get(key.as_str()) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(value)) => escape_tsv(value),
+                    Some(value) => escape_tsv(&value.to_string()),
+                })
+                .collect::<Vec<_>>()
+                .join("\t"),
+        );
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn escape_tsv(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+    use test_case::test_case;
+
+    use crate::output::{to_tsv, Format};
+
+    #[test_case("human", Some(Format::Human))]
+    #[test_case("json", Some(Format::Json))]
+    #[test_case("yaml", Some(Format::Yaml))]
+    #[test_case("tsv", Some(Format::Tsv))]
+    #[test_case("xml", None)]
+    fn test_from_str(input: &str, expected: Option<Format>) {
+        assert_eq!(expected, Format::from_str(input).ok());
+    }
+
+    #[test]
+    fn test_to_tsv() {
+        let records = vec![
+            json!({"id": 1, "name": "first", "url": null}),
+            json!({"id": 2, "name": "tab\tand\nnewline", "url": "example.org"}),
+        ];
+
+        assert_eq!(
+            "id\tname\turl\n1\tfirst\t\n2\ttab\\tand\\nnewline\texample.org\n",
+            to_tsv(&records)
+        );
+    }
+
+    #[test]
+    fn test_to_tsv_keys_of_later_records() {
+        let records = vec![json!({"id": 1}), json!({"id": 2, "error": "failed"})];
+
+        assert_eq!("id\terror\n1\t\n2\tfailed\n", to_tsv(&records));
+    }
+
+    #[test]
+    fn test_to_tsv_empty() {
+        assert_eq!("", to_tsv(&[]));
+    }
+}
@@ -1,13 +1,14 @@
-use std::process;
-
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::NaiveDate;
 use clap::ArgMatches;
 use dialoguer::{theme::ColorfulTheme, Input, Password};
 use log::warn;
 use passwords::{analyzer, scorer};
 
+use crate::api;
+use crate::helper::{end_of_day, parse_date};
+
 #[allow(clippy::module_name_repetitions)]
-pub fn ask_prompt(text: &str, required: bool, default: &str) -> String {
+pub fn ask_prompt(text: &str, required: bool, default: &str) -> Result<String, api::Error> {
     let theme = ColorfulTheme::default();
     let mut prompt = Input::with_theme(&theme).with_prompt(text).allow_empty(!required);
 
@@ -15,9 +16,7 @@ pub fn ask_prompt(text: &str, required: bool, default: &str) -> String {
         prompt = prompt.with_initial_text(default);
     }
 
-    prompt.interact_text().unwrap_or_else(|_| {
-        process::exit(1);
-    })
+    prompt.interact_text().map_err(|_| api::Error::Aborted)
 }
 
 pub fn get_match_string(
@@ -27,19 +26,19 @@ pub fn get_match_string(
     prompt_text: &str,
     default: &str,
     required: bool,
-) -> String {
+) -> Result<String, api::Error> {
     if let Some(description) = matches.get_one::<String>(match_id) {
         if description.is_empty() && !quiet {
             ask_prompt(prompt_text, required, default)
         } else {
-            description.clone()
+            Ok(description.clone())
         }
     } else {
         if !quiet {
             return ask_prompt(prompt_text, required, default);
         }
 
-        default.to_owned()
+        Ok(default.to_owned())
     }
 }
 
@@ -48,7 +47,7 @@ pub fn ask_for_date(prompt: &str, date: NaiveDate) -> String {
         .with_initial_text(date.format("%Y-%m-%d").to_string())
         .with_prompt(prompt)
         .validate_with(|input: &String| -> Result<(), &str> {
-            if input.is_empty() || NaiveDateTime::parse_from_str(input, "%Y-%m-%d").is_ok() {
+            if input.is_empty() || parse_date(input).is_ok() {
                 Ok(())
             } else {
                 Err("Please enter a valid date in YYYY-mm-dd format or leave it empty.")
@@ -57,33 +56,34 @@ pub fn ask_for_date(prompt: &str, date: NaiveDate) -> String {
         .interact_text()
         .unwrap_or_else(|_| String::new());
 
-    if !date.is_empty() {
-        return NaiveDateTime::parse_from_str(date.as_str(), "%Y-%m-%d")
-            .expect("Invalid date")
-            .and_utc()
-            .timestamp()
-            .to_string();
-    }
+    parse_date(&date).map_or_else(|_| String::new(), |date| end_of_day(date).to_string())
+}
 
-    String::new()
+pub fn ask_for_password(prompt: &str, confirm: bool) -> Result<String, api::Error> {
+    ask_for_checked_password(prompt, confirm, |_| Ok(()))
 }
 
-pub fn ask_for_password(prompt: &str, confirm: bool) -> String {
+// Typed passwords the check refuses are asked for again
+pub fn ask_for_checked_password(
+    prompt: &str,
+    confirm: bool,
+    check: impl Fn(&str) -> Result<(), String>,
+) -> Result<String, api::Error> {
     let theme = ColorfulTheme::default();
     let mut password =
         Password::with_theme(&theme)
             .with_prompt(prompt)
-            .validate_with(|input: &String| -> Result<(), &str> {
+            .validate_with(|input: &String| -> Result<(), String> {
                 let strength = password_strength(scorer::score(&analyzer::analyze(input)));
                 warn!("Password strength: {}", strength);
-                Ok(())
+                check(input)
             });
 
     if confirm {
         password = password.with_confirmation("Repeat password", "Error: the passwords don't match.");
     }
 
-    password.interact().expect("Failed to get password")
+    password.interact().map_err(|_| api::Error::Aborted)
 }
 
 pub fn password_strength(strength: f64) -> String {
@@ -0,0 +1,81 @@
+use std::sync::atomic::Ordering;
+
+use serde_json::Value;
+
+use crate::DEBUG_UNSAFE;
+
+const SECRET_KEYS: [&str; 5] = ["authToken", "tokenPass", "pass", "password", "account_pass"];
+const MASK: &str = "********";
+
+pub fn redact(value: Value) -> Value {
+    if DEBUG_UNSAFE.load(Ordering::Relaxed) {
+        value
+    } else {
+        mask(value)
+    }
+}
+
+pub fn mask(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    if SECRET_KEYS.contains(&key.as_str()) && !value.is_null() {
+                        (key, Value::from(MASK))
+                    } else {
+                        (key, mask(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(list) => Value::Array(list.into_iter().map(mask).collect()),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::redact::mask;
+
+    #[test]
+    fn test_mask() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "account/viewPass",
+            "params": {"authToken": "token", "tokenPass": "secret", "id": "1"},
+            "id": 1
+        });
+
+        assert_eq!(
+            json!({
+                "jsonrpc": "2.0",
+                "method": "account/viewPass",
+                "params": {"authToken": "********", "tokenPass": "********", "id": "1"},
+                "id": 1
+            }),
+            mask(request)
+        );
+    }
+
+    #[test]
+    fn test_mask_nested() {
+        let response = json!({
+            "result": {
+                "result": [{"password": "secret", "name": "test"}, {"account_pass": "secret", "pass": null}],
+                "itemId": 1
+            }
+        });
+
+        assert_eq!(
+            json!({
+                "result": {
+                    "result": [{"password": "********", "name": "test"}, {"account_pass": "********", "pass": null}],
+                    "itemId": 1
+                }
+            }),
+            mask(response)
+        );
+    }
+}
@@ -1,16 +1,24 @@
 use std::error::Error;
 
 use clap::{arg, ArgMatches, Command, ValueHint};
+use serde::Serialize;
 
-use crate::api::{AppError, Client};
+use crate::api::{self, AppError, Client};
 use crate::helper;
 
 mod account;
 mod category;
 mod client;
+mod tag;
 
 pub const COMMAND_NAME: &str = "remove";
 
+#[derive(Serialize)]
+struct Removed {
+    id: u32,
+    removed: bool,
+}
+
 pub fn command_helper() -> Command {
     Command::new(COMMAND_NAME)
         .visible_alias("delete")
@@ -26,10 +34,11 @@ pub fn command_helper() -> Command {
         .subcommand(client::command_helper())
         .subcommand(category::command_helper())
         .subcommand(account::command_helper())
+        .subcommand(tag::command_helper())
 }
 
 pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
-    let id: u32 = helper::get_numeric_input("id", matches, false, None::<fn() -> u32>, quiet);
+    let id: u32 = helper::get_numeric_input("id", matches, false, None::<fn() -> Result<u32, api::Error>>, quiet)?;
     if id == 0 {
         Err(AppError("Invalid id given".to_owned()))?;
     }
@@ -38,6 +47,7 @@ pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Re
         Some((account::COMMAND_NAME, matches)) => account::command(matches, api_client, id),
         Some((client::COMMAND_NAME, matches)) => client::command(matches, api_client, id),
         Some((category::COMMAND_NAME, matches)) => category::command(matches, api_client, id),
+        Some((tag::COMMAND_NAME, matches)) => tag::command(matches, api_client, id),
         _ => unreachable!("Clap should keep us out from here"),
     }
 }
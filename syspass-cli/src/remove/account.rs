@@ -4,6 +4,10 @@ use clap::{ArgMatches, Command};
 use colored::Colorize;
 use log::warn;
 
+use crate::cache;
+use crate::output::Format;
+use crate::remove::Removed;
+
 pub const COMMAND_NAME: &str = "password";
 
 pub fn command_helper() -> Command {
@@ -13,10 +17,12 @@ pub fn command_helper() -> Command {
         .about("Remove account")
 }
 
-pub fn command(_matches: &ArgMatches, api_client: &dyn crate::api::Client, id: u32) -> Result<u8, Box<dyn Error>> {
+pub fn command(matches: &ArgMatches, api_client: &dyn crate::api::Client, id: u32) -> Result<u8, Box<dyn Error>> {
     match api_client.delete_account(id) {
         Ok(status) => {
+            Format::from(matches).print_one(&Removed { id, removed: status })?;
             if status {
+                cache::invalidate(api_client.get_config());
                 warn!("{} Account removed", "\u{2714}".bright_green());
             } else {
                 warn!("{} Failed to remove account", "\u{2716}".bright_red());
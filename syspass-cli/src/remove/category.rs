@@ -4,16 +4,22 @@ use clap::{ArgMatches, Command};
 use colored::Colorize;
 use log::warn;
 
+use crate::cache;
+use crate::output::Format;
+use crate::remove::Removed;
+
 pub const COMMAND_NAME: &str = "category";
 
 pub fn command_helper() -> Command {
     Command::new(COMMAND_NAME).about("Remove category").short_flag('a')
 }
 
-pub fn command(_matches: &ArgMatches, api_client: &dyn crate::api::Client, id: u32) -> Result<u8, Box<dyn Error>> {
+pub fn command(matches: &ArgMatches, api_client: &dyn crate::api::Client, id: u32) -> Result<u8, Box<dyn Error>> {
     match api_client.delete_category(id) {
         Ok(status) => {
+            Format::from(matches).print_one(&Removed { id, removed: status })?;
             if status {
+                cache::invalidate(api_client.get_config());
                 warn!("{} Category removed", "\u{2714}".bright_green());
             } else {
                 warn!("{} Failed to remove category", "\u{2716}".bright_red());
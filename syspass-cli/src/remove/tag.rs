@@ -0,0 +1,32 @@
+use std::error::Error;
+
+use clap::{ArgMatches, Command};
+use colored::Colorize;
+use log::warn;
+
+use crate::output::Format;
+use crate::remove::Removed;
+
+pub const COMMAND_NAME: &str = "tag";
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME).about("Remove tag").short_flag('t')
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn crate::api::Client, id: u32) -> Result<u8, Box<dyn Error>> {
+    match api_client.delete_tag(id) {
+        Ok(status) => {
+            Format::from(matches).print_one(&Removed { id, removed: status })?;
+            if status {
+                warn!("{} Tag removed", "\u{2714}".bright_green());
+            } else {
+                warn!("{} Failed to remove tag", "\u{2716}".bright_red());
+            }
+        }
+        Err(error) => {
+            Err(error)?;
+        }
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,365 @@
+use std::error::Error;
+use std::io::{ErrorKind, Write};
+use std::process::{self, Stdio};
+
+use chrono::Utc;
+use clap::{arg, ArgGroup, ArgMatches, Command, ValueHint};
+use colored::Colorize;
+use log::{error, info, warn};
+use serde::Serialize;
+
+use crate::api::account::{Account, ChangePassword};
+use crate::api::entity::Entity;
+use crate::api::{self, Client};
+use crate::edit::edit_password::generate_password;
+use crate::generate::{get_policy, Policy};
+use crate::helper::{parse_days, DAY};
+use crate::output::Format;
+use crate::strength::{enforce, Checks};
+
+pub const COMMAND_NAME: &str = "rotate";
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum Status {
+    WouldRotate,
+    Rotated,
+    Failed,
+    RolledBack,
+    RollbackFailed,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rotation {
+    id: u32,
+    name: String,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn command_helper() -> Command {
+    Command::new(COMMAND_NAME)
+        .about("Generate new passwords, apply them with a hook and save them. Requires sysPass 3")
+        .arg(
+            arg!([ID] "Account id")
+                .value_parser(clap::value_parser!(u32))
+                .conflicts_with_all(["category", "older-than"])
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(-a --category <CATEGORYID> "Rotate all accounts of the category")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .value_hint(ValueHint::Other),
+        )
+        .arg(
+            arg!(--"older-than" <DAYS> "Only rotate passwords last changed before this many days, e.g. 90d")
+                .required(false)
+                .value_parser(parse_days)
+                .value_hint(ValueHint::Other),
+        )
+        .group(
+            ArgGroup::new("accounts")
+                .args(["ID", "category", "older-than"])
+                .multiple(true)
+                .required(true),
+        )
+        .arg(
+            arg!(--hook <COMMAND> "Command that applies the password to the target system, defaults to rotateHook")
+                .required(false)
+                .value_hint(ValueHint::CommandString),
+        )
+        .arg(arg!(--"no-hook" "Only change the password in sysPass").conflicts_with("hook"))
+        .arg(
+            arg!(-e --"expires-in" <DAYS> "Expire the new password after this many days, 0 for never. Keeps the previous interval by default")
+                .required(false)
+                .value_parser(parse_days)
+                .value_hint(ValueHint::Other),
+        )
+        .arg(arg!(--"allow-weak" "Save a password that doesn't meet passwordRequirements"))
+        .arg(arg!(--"dry-run" "Only list the accounts that would be rotated"))
+}
+
+pub fn command(matches: &ArgMatches, api_client: &dyn Client) -> Result<u8, Box<dyn Error>> {
+    // sysPass 2 can't change passwords, which must be known before the hook touches the target system
+    if !api_client.can_change_password() {
+        Err(api::Error::Unsupported)?;
+    }
+
+    let dry_run = matches.get_flag("dry-run");
+    // A dry run never calls the hook, so it doesn't need one
+    let hook = if dry_run || matches.get_flag("no-hook") {
+        None
+    } else {
+        Some(
+            matches
+                .get_one::<String>("hook")
+                .or_else(|| api_client.get_config().rotate_hook.as_ref())
+                .ok_or("No rotate hook configured, set rotateHook, use --hook or --no-hook")?
+                .as_str(),
+        )
+    };
+
+    let config = api_client.get_config();
+    let policy = get_policy(config, None)?;
+    let mut checks = Checks::new(config, None)?;
+    let allow_weak = matches.get_flag("allow-weak");
+    let now = Utc::now().timestamp();
+    let accounts = get_accounts(matches, api_client, now)?;
+    let expires_in = matches.get_one::<u32>("expires-in").copied();
+
+    let rotations: Vec<_> = accounts
+        .iter()
+        .map(|account| {
+            if dry_run {
+                return rotation(account, Status::WouldRotate, None);
+            }
+
+            let expire_date = get_expire_date(account, now, expires_in);
+            checks.requirements = config
+                .password_requirements
+                .as_ref()
+                .map(|requirements| requirements.for_category(Some(*account.category_id())));
+            match rotate(api_client, hook, account, policy, &checks, allow_weak, expire_date) {
+                Ok(()) => rotation(account, Status::Rotated, None),
+                Err((status, error)) => rotation(account, status, Some(error)),
+            }
+        })
+        .collect();
+
+    print_rotations(&rotations, Format::from(matches))?;
+
+    Ok(u8::from(rotations.iter().any(|rotation| {
+        !matches!(rotation.status, Status::Rotated | Status::WouldRotate)
+    })))
+}
+
+fn get_accounts(matches: &ArgMatches, api_client: &dyn Client, now: i64) -> Result<Vec<Account>, api::Error> {
+    if let Some(id) = matches.get_one::<u32>("ID") {
+        return Ok(vec![api_client.view_account(*id)?]);
+    }
+
+    let mut search = vec![("count", api::ACCOUNT_LIMIT.to_owned())];
+    if let Some(category) = matches.get_one::<u32>("category") {
+        search.push(("categoryId", category.to_string()));
+    }
+    let mut accounts = api_client.search_account(search, false)?;
+    if let Some(days) = matches.get_one::<u32>("older-than") {
+        accounts.retain(|account| is_older(account, now, *days));
+    }
+
+    Ok(accounts)
+}
+
+fn is_older(account: &Account, now: i64, days: u32) -> bool {
+    account
+        .pass_date()
+        .is_some_and(|changed| changed <= now - i64::from(days) * DAY)
+}
+
+fn get_expire_date(account: &Account, now: i64, expires_in: Option<u32>) -> i64 {
+    match (expires_in, account.pass_date(), account.expire_date()) {
+        (Some(0), _, _) => 0,
+        (Some(days), _, _) => now + i64::from(days) * DAY,
+        (None, Some(changed), Some(expires)) if expires > changed => now + expires - changed,
+        _ => 0,
+    }
+}
+
+fn rotation(account: &Account, status: Status, error: Option<String>) -> Rotation {
+    Rotation {
+        id: account.id().copied().unwrap_or_default(),
+        name: account.name().to_owned(),
+        status,
+        error,
+    }
+}
+
+// The new password must pass the same checks as a typed one before the hook sees it.
+// The target system is changed first, if sysPass then refuses the new password the hook is run again to restore it
+fn rotate(
+    api_client: &dyn Client,
+    hook: Option<&str>,
+    account: &Account,
+    policy: Option<&Policy>,
+    checks: &Checks,
+    allow_weak: bool,
+    expire_date: i64,
+) -> Result<(), (Status, String)> {
+    let id = account.id().copied().unwrap_or_default();
+    let password = generate_password(policy);
+    enforce(checks, &password, allow_weak).map_err(|error| (Status::Failed, error))?;
+    let current = api_client
+        .get_password(account)
+        .map_err(|error| (Status::Failed, format!("Could not read the current password: {error}")))?
+        .password;
+
+    if let Some(hook) = hook {
+        run_hook(hook, account, &password, &current, false).map_err(|error| (Status::Failed, error))?;
+    }
+
+    let change = ChangePassword {
+        id,
+        pass: password.clone(),
+        expire_date,
+    };
+    let Err(error) = api_client.change_password(&change) else {
+        return Ok(());
+    };
+
+    let Some(hook) = hook else {
+        return Err((Status::Failed, error.to_string()));
+    };
+    match run_hook(hook, account, &current, &password, true) {
+        Ok(()) => Err((Status::RolledBack, error.to_string())),
+        Err(hook_error) => {
+            error!(
+                "Rollback of {id}. {} failed, the target system may still use the new password. \
+                 Run the hook by hand with the current password from `syspass-cli get {id}`",
+                account.name()
+            );
+            Err((Status::RollbackFailed, format!("{error}, {hook_error}")))
+        }
+    }
+}
+
+// The hook reads the password to set and the one it replaces from the first two lines of stdin
+fn run_hook(hook: &str, account: &Account, password: &str, current: &str, rollback: bool) -> Result<(), String> {
+    let mut command = if cfg!(windows) {
+        let mut command = process::Command::new("cmd");
+        command.args(["/C", hook]);
+        command
+    } else {
+        let mut command = process::Command::new("sh");
+        command.args(["-c", hook]);
+        command
+    };
+
+    let mut child = command
+        .env(
+            "SYSPASS_ACCOUNT_ID",
+            account.id().copied().unwrap_or_default().to_string(),
+        )
+        .env("SYSPASS_ACCOUNT_NAME", account.name())
+        .env("SYSPASS_ACCOUNT_LOGIN", account.login())
+        .env("SYSPASS_ACCOUNT_URL", account.url().unwrap_or_default())
+        .env("SYSPASS_ROLLBACK", if rollback { "1" } else { "0" })
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("Failed to run hook: {error}"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(format!("{password}\n{current}\n").as_bytes()) {
+            Err(error) if error.kind() != ErrorKind::BrokenPipe => return Err(format!("Failed to run hook: {error}")),
+            _ => {}
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|error| format!("Failed to run hook: {error}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !stdout.trim().is_empty() {
+        info!("{}", stdout.trim());
+    }
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Hook failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn print_rotations(rotations: &[Rotation], format: Format) -> Result<(), Box<dyn Error>> {
+    if format.is_structured() {
+        return format.print_list(rotations);
+    }
+
+    if rotations.is_empty() {
+        warn!("{} No passwords to rotate", "\u{2714}".bright_green());
+    }
+    for rotation in rotations {
+        let account = format!("{}. {}", rotation.id, rotation.name);
+        match (&rotation.status, &rotation.error) {
+            (Status::WouldRotate, _) => warn!("Would rotate {account}"),
+            (Status::Rotated, _) => warn!("{} Rotated {account}", "\u{2714}".bright_green()),
+            (Status::RolledBack, Some(error)) => {
+                warn!("{} Rolled back {account}: {error}", "\u{2716}".bright_red());
+            }
+            (_, error) => warn!(
+                "{} Could not rotate {account}: {}",
+                "\u{2716}".bright_red(),
+                error.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use crate::api::account::Account;
+    use crate::helper::DAY;
+    use crate::rotate::{get_expire_date, is_older, run_hook};
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn account(pass_date: Option<i64>, pass_date_change: i64) -> Account {
+        serde_json::from_value(serde_json::json!({
+            "id": 7,
+            "name": "router",
+            "login": "admin",
+            "categoryId": 1,
+            "clientId": 1,
+            "passDate": pass_date,
+            "passDateChange": pass_date_change,
+        }))
+        .expect("Deserialization failed")
+    }
+
+    #[test_case(Some(NOW - 100 * DAY), 90, true)]
+    #[test_case(Some(NOW - 90 * DAY), 90, true)]
+    #[test_case(Some(NOW - 10 * DAY), 90, false)]
+    #[test_case(None, 0, false)]
+    fn test_is_older(pass_date: Option<i64>, days: u32, expected: bool) {
+        assert_eq!(expected, is_older(&account(pass_date, 0), NOW, days));
+    }
+
+    #[test_case(Some(NOW - DAY), NOW + 89 * DAY, None, NOW + 90 * DAY; "keeps interval")]
+    #[test_case(Some(NOW - DAY), 0, None, 0; "never expired")]
+    #[test_case(Some(NOW - DAY), NOW + 89 * DAY, Some(30), NOW + 30 * DAY; "expires in")]
+    #[test_case(Some(NOW - DAY), NOW + 89 * DAY, Some(0), 0; "expires never")]
+    fn test_get_expire_date(pass_date: Option<i64>, pass_date_change: i64, expires_in: Option<u32>, expected: i64) {
+        assert_eq!(
+            expected,
+            get_expire_date(&account(pass_date, pass_date_change), NOW, expires_in)
+        );
+    }
+
+    #[test]
+    fn test_run_hook() {
+        let hook = r#"read new; read old; test "$new" = new-pass && test "$old" = old-pass && test "$SYSPASS_ACCOUNT_LOGIN" = admin && test "$SYSPASS_ROLLBACK" = 1"#;
+
+        assert!(run_hook(hook, &account(None, 0), "new-pass", "old-pass", true).is_ok());
+        assert!(run_hook(hook, &account(None, 0), "new-pass", "old-pass", false).is_err());
+        assert!(run_hook(
+            "echo refused >&2; exit 2",
+            &account(None, 0),
+            "new-pass",
+            "old-pass",
+            false
+        )
+        .is_err_and(|error| error.contains("refused")));
+    }
+}
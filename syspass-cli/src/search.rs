@@ -7,15 +7,17 @@ use arboard::Clipboard;
 use clap::{arg, Arg, ArgAction, ArgMatches, Command, ValueHint};
 use colored::Colorize;
 use dialoguer::{theme::ColorfulTheme, FuzzySelect};
-use log::{error, warn};
+use log::warn;
 use term_table::row::Row;
 use term_table::table_cell::{Alignment, TableCell};
 use term_table::{Table, TableStyle};
 
 use crate::api::account::{Account, ViewPassword};
 use crate::api::entity::Entity;
-use crate::api::{AppError, Client};
+use crate::api::{self, AppError, Client};
+use crate::cache;
 use crate::config::Config;
+use crate::output::Format;
 use crate::{DEFAULT_TERMINAL_SIZE, TERMINAL_SIZE};
 
 pub const COMMAND_NAME: &str = "search";
@@ -52,6 +54,11 @@ pub fn command_helper() -> Command {
                 .value_hint(ValueHint::Other)
                 .value_parser(clap::value_parser!(u32)),
         )
+        .arg(
+            arg!(-t --tag <TAGID> "Tag id")
+                .value_hint(ValueHint::Other)
+                .value_parser(clap::value_parser!(u32)),
+        )
         .arg(
             Arg::new("disable-usage")
                 .short('u')
@@ -59,6 +66,12 @@ pub fn command_helper() -> Command {
                 .long("disable-usage")
                 .help("Do not sort account list by usage and do not track usage history"),
         )
+        .arg(
+            Arg::new("no-cache")
+                .action(ArgAction::SetTrue)
+                .long("no-cache")
+                .help("Search on the server even if the local cache is fresh"),
+        )
         .arg(
             arg!(--clear "Clear clipboard")
                 .hide(true)
@@ -66,20 +79,6 @@ pub fn command_helper() -> Command {
         )
 }
 
-fn get_accounts_list(
-    api_client: &dyn Client,
-    search_string: Vec<(&str, String)>,
-    usage_disabled: bool,
-) -> Vec<Account> {
-    match api_client.search_account(search_string, usage_disabled) {
-        Ok(accounts) => accounts,
-        Err(error) => {
-            error!("{} Error while searching: {}", "\u{2716}".bright_red(), error);
-            process::exit(1);
-        }
-    }
-}
-
 fn clear_clipboard(timeout: u64, immediate: bool) -> Result<u8, Box<dyn Error>> {
     if timeout > 0 {
         if !immediate {
@@ -96,6 +95,27 @@ fn clear_clipboard(timeout: u64, immediate: bool) -> Result<u8, Box<dyn Error>>
     Ok(0)
 }
 
+// Copies the text and clears it again after passwordTimeout seconds
+pub fn copy_to_clipboard(text: &str, config: &Config) -> Result<(), Box<dyn Error>> {
+    if let Ok(mut clipboard) = Clipboard::new() {
+        clipboard.set_text(text).expect("Couldn't set password");
+        thread::sleep(Duration::from_millis(10)); // KDE / Wayland clipboard fix
+    }
+
+    if config.password_timeout.unwrap_or(10) > 0 {
+        if let Some(path) = env::current_exe()?.as_path().to_str() {
+            let mut child = process::Command::new(path)
+                .args(["search", "--clear"])
+                .spawn()
+                .expect("Failed to start child");
+
+            child.wait().expect("Failed to wait for child");
+        }
+    }
+
+    Ok(())
+}
+
 pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Result<u8, Box<dyn Error>> {
     let name = matches
         .get_one::<String>("name")
@@ -104,6 +124,7 @@ pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Re
     let category: u32 = matches
         .get_one::<u32>("category")
         .map_or(0, std::borrow::ToOwned::to_owned);
+    let tag: u32 = matches.get_one::<u32>("tag").map_or(0, std::borrow::ToOwned::to_owned);
     let config = api_client.get_config();
     let show = matches.get_flag("show-password") || config.no_clipboard;
 
@@ -114,69 +135,66 @@ pub fn command(matches: &ArgMatches, api_client: &dyn Client, quiet: bool) -> Re
     let accounts: Vec<Account>;
 
     if id > 0 {
-        accounts = vec![api_client.view_account(id).expect("Invalid account id")];
-    } else if name.is_empty() {
-        warn!("{} {}", "\u{2716}".bright_red(), "Name or id is required".red());
-        process::exit(1);
+        accounts = vec![api_client.view_account(id)?];
+    } else if name.is_empty() && tag == 0 {
+        return Err(AppError("Name, tag or id is required".to_owned()).into());
     } else {
-        let mut search_string = vec![("text", name)];
+        let mut search_string = vec![("text", name.clone())];
         if category > 0 {
             search_string.push(("categoryId", category.to_string()));
         }
+        if tag > 0 {
+            search_string.push(("tagsId", tag.to_string()));
+        }
 
-        accounts = get_accounts_list(api_client, search_string, !matches.get_flag("disable-usage"));
+        let usage = !matches.get_flag("disable-usage");
+        let cached = if tag > 0 || matches.get_flag("no-cache") {
+            None
+        } else {
+            cache::search(matches, config, &name, category, usage)
+        };
+
+        accounts = match cached {
+            Some(accounts) => accounts,
+            None => api_client.search_account(search_string, usage)?,
+        };
     }
 
+    let format = Format::from(matches);
+
     if accounts.len() > 1 && quiet {
+        format.print_list(&accounts)?;
         return Ok(1);
     }
 
     let account: ViewPassword = {
         if accounts.len() > 1 {
-            match select_account(&accounts, api_client, matches.get_flag("disable-usage")) {
-                Ok(account) => account,
-                Err(error) => {
-                    error!("{} Error while searching: {}", "\u{2716}".bright_red(), error);
-                    process::exit(1);
-                }
-            }
+            select_account(&accounts, api_client, matches)?
         } else {
             let Some(account) = accounts.first() else {
-                warn!("{} No account found", "\u{2716}".bright_red());
-                process::exit(1);
+                return Err(api::Error::NotFound("No account found".to_owned()).into());
             };
 
-            match api_client.get_password(account) {
-                Ok(password) => password,
-                Err(error) => {
-                    error!("{} Error while searching: {}", "\u{2716}".bright_red(), error);
-                    process::exit(1);
-                }
-            }
+            api_client.get_password(account)?
         }
     };
 
     if !show {
-        if let Ok(mut clipboard) = Clipboard::new() {
-            clipboard.set_text(&account.password).expect("Couldn't set password");
-            thread::sleep(Duration::from_millis(10)); // KDE / Wayland clipboard fix
-        }
-
-        if config.password_timeout.unwrap_or(10) > 0 {
-            if let Some(path) = env::current_exe()?.as_path().to_str() {
-                let mut child = process::Command::new(path)
-                    .args(["search", "--clear"])
-                    .spawn()
-                    .expect("Failed to start child");
-
-                child.wait().expect("Failed to wait for child");
-            }
-        }
+        copy_to_clipboard(&account.password, config)?;
     }
 
-    warn!("{}", print_table_for_account(&account, show));
+    if !format.is_structured() {
+        warn!("{}", print_table_for_account(&account, show));
+    } else if show {
+        format.print_one(&account)?;
+    } else {
+        format.print_one(&account.account)?;
+    }
 
-    if !config.no_shell && !matches.get_flag("no-shell") && account.account.url().unwrap_or_default().contains("ssh://")
+    if !format.is_structured()
+        && !config.no_shell
+        && !matches.get_flag("no-shell")
+        && account.account.url().unwrap_or_default().contains("ssh://")
     {
         open_shell(&account.account);
     }
@@ -197,8 +215,8 @@ fn open_shell(account: &Account) {
 fn select_account(
     accounts: &[Account],
     api_client: &dyn Client,
-    disable_usage: bool,
-) -> Result<ViewPassword, AppError> {
+    matches: &ArgMatches,
+) -> Result<ViewPassword, api::Error> {
     let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
         .with_prompt("Select the right account:")
         .default(0)
@@ -208,12 +226,16 @@ fn select_account(
 
     match selection {
         Ok(choice) => {
-            if !disable_usage {
-                Config::record_usage(*accounts[choice].id().expect("Id should be set"), None);
+            if !matches.get_flag("disable-usage") {
+                Config::record_usage(
+                    *accounts[choice].id().expect("Id should be set"),
+                    api_client.get_config().profile.as_deref(),
+                    None,
+                );
             }
             Ok(api_client.get_password(&accounts[choice])?)
         }
-        Err(err) => Err(AppError(err.to_string())),
+        Err(_) => Err(api::Error::Aborted),
     }
 }
 
@@ -324,7 +346,7 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
+    #[ignore = "Requires a clipboard"]
     fn test_clear_clipboard() {
         let mut clipboard = Clipboard::new().expect("Failed to open clipboard");
         clipboard.set_text("testing").expect("Failed to set clipboard value");
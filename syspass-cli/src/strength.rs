@@ -0,0 +1,201 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use log::warn;
+use passwords::{analyzer, scorer};
+use serde::{Deserialize, Serialize};
+
+use crate::breach::BreachFile;
+use crate::config::{Config, LoadError};
+use crate::prompt::password_strength;
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Requirements {
+    pub min_score: f64,
+    pub min_length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub numbers: bool,
+    pub symbols: bool,
+    // Replace the requirements for the accounts of a category id
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub categories: BTreeMap<u32, Self>,
+}
+
+impl Requirements {
+    pub fn for_category(&self, category: Option<u32>) -> &Self {
+        category
+            .and_then(|category| self.categories.get(&category))
+            .unwrap_or(self)
+    }
+
+    pub fn check(&self, password: &str) -> Result<(), String> {
+        let analyzed = analyzer::analyze(password);
+        let score = scorer::score(&analyzed);
+        let mut problems = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            problems.push(format!("shorter than {} characters", self.min_length));
+        }
+        if score < self.min_score {
+            problems.push(format!(
+                "strength {} ({score:.0}) is below {}",
+                password_strength(score),
+                self.min_score
+            ));
+        }
+        for (required, count, class) in [
+            (self.lowercase, analyzed.lowercase_letters_count(), "lowercase letters"),
+            (self.uppercase, analyzed.uppercase_letters_count(), "uppercase letters"),
+            (self.numbers, analyzed.numbers_count(), "numbers"),
+            (self.symbols, analyzed.symbols_count(), "symbols"),
+        ] {
+            if required && count == 0 {
+                problems.push(format!("no {class}"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Password rejected: {}", problems.join(", ")))
+        }
+    }
+}
+
+// The configured requirements and breach file a new password is checked against
+#[derive(Default)]
+pub struct Checks<'a> {
+    pub requirements: Option<&'a Requirements>,
+    pub breach_file: Option<RefCell<BreachFile>>,
+}
+
+impl<'a> Checks<'a> {
+    // The breach file is opened up front, a missing or invalid one is a config error and not a weak password
+    pub fn new(config: &'a Config, category: Option<u32>) -> Result<Self, LoadError> {
+        let breach_file = config
+            .breach_file
+            .as_deref()
+            .map(BreachFile::open)
+            .transpose()
+            .map_err(|error| LoadError(format!("Invalid breachFile: {error}")))?;
+
+        Ok(Self {
+            requirements: config
+                .password_requirements
+                .as_ref()
+                .map(|requirements| requirements.for_category(category)),
+            breach_file: breach_file.map(RefCell::new),
+        })
+    }
+
+    // Why the password is refused, Err only when the breach file can't be read
+    fn find_problem(&self, password: &str) -> Result<Option<String>, String> {
+        if let Some(Err(problem)) = self.requirements.map(|requirements| requirements.check(password)) {
+            return Ok(Some(problem));
+        }
+
+        let count = match &self.breach_file {
+            Some(breach_file) => breach_file.borrow_mut().count(password)?,
+            None => 0,
+        };
+
+        Ok((count > 0).then(|| format!("Password appears {count} times in known breaches")))
+    }
+
+    pub fn check(&self, password: &str) -> Result<(), String> {
+        self.find_problem(password)?.map_or(Ok(()), Err)
+    }
+}
+
+// Passwords that fail the checks are only accepted with --allow-weak, which is logged.
+// An unreadable breach file is always an error
+pub fn enforce(checks: &Checks, password: &str, allow_weak: bool) -> Result<(), String> {
+    match checks.find_problem(password)? {
+        Some(problem) if allow_weak => {
+            warn!("{problem}. Accepted with --allow-weak");
+            Ok(())
+        }
+        Some(problem) => Err(format!("{problem}. Use --allow-weak to save it anyway")),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use std::io::Write;
+
+    use crate::config::Config;
+    use crate::strength::{enforce, Checks, Requirements};
+
+    fn requirements() -> Requirements {
+        serde_json::from_str(
+            r#"{
+                "minScore": 80,
+                "minLength": 12,
+                "symbols": true,
+                "categories": {"5": {"minLength": 6, "numbers": true}}
+            }"#,
+        )
+        .expect("Valid requirements")
+    }
+
+    #[test_case("Dg7#kL2!pQ9zWm4x", None, true)]
+    #[test_case("Dg7kL2pQ9zWm4xYt", None, false; "no symbols")]
+    #[test_case("Dg7#kL2!", None, false; "too short")]
+    #[test_case("password", Some(5), false; "no numbers")]
+    #[test_case("123456", Some(5), true)]
+    #[test_case("Dg7#kL2!pQ9zWm4x", Some(7), true; "unknown category")]
+    fn test_check(password: &str, category: Option<u32>, expected: bool) {
+        assert_eq!(expected, requirements().for_category(category).check(password).is_ok());
+    }
+
+    #[test]
+    fn test_check_message() {
+        let error = requirements().check("abc").expect_err("Password is weak");
+
+        assert!(error.contains("shorter than 12 characters"));
+        assert!(error.contains("Very dangerous"));
+        assert!(error.contains("no symbols"));
+    }
+
+    #[test]
+    fn test_enforce() {
+        let requirements = requirements();
+        let checks = Checks {
+            requirements: Some(&requirements),
+            breach_file: None,
+        };
+
+        assert!(enforce(&Checks::default(), "abc", false).is_ok());
+        assert!(enforce(&checks, "abc", false).is_err_and(|error| error.contains("--allow-weak")));
+        assert!(enforce(&checks, "abc", true).is_ok());
+    }
+
+    #[test]
+    fn test_breach_file() {
+        let mut file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        // SHA-1 of "password"
+        writeln!(file, "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:3").expect("Failed to write breach file");
+        let config = Config {
+            breach_file: Some(file.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let checks = Checks::new(&config, None).expect("Breach file should open");
+
+        assert!(checks.check("password").is_err_and(|error| error.contains("3 times")));
+        assert!(checks.check("Dg7#kL2!pQ9zWm4x").is_ok());
+        assert!(enforce(&checks, "password", false).is_err());
+        assert!(enforce(&checks, "password", true).is_ok());
+
+        let config = Config {
+            breach_file: Some("/nonexistent/breach.txt".to_owned()),
+            ..Default::default()
+        };
+        assert!(Checks::new(&config, None).is_err_and(|error| error.0.contains("Invalid breachFile")));
+    }
+}
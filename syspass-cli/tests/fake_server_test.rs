@@ -0,0 +1,852 @@
+mod fake_syspass;
+
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::{tempdir, TempDir};
+use test_case::test_case;
+
+use crate::fake_syspass::{write_config, Failure, FakeSyspass, Version, PASSWORD, READ_ONLY_TOKEN, TOKEN};
+
+struct Cli {
+    home: TempDir,
+    config: String,
+}
+
+impl Cli {
+    fn new(server: &FakeSyspass, token: &str, password: &str) -> Self {
+        let home = tempdir().expect("Failed to create temp dir");
+        let config = server
+            .write_config(home.path(), token, password)
+            .to_string_lossy()
+            .to_string();
+
+        Self { home, config }
+    }
+
+    fn run(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("syspass-cli")
+            .expect("Command should not have failed")
+            .env_clear()
+            .env("HOME", self.home.path())
+            .args(["-q", "-c", &self.config])
+            .args(args)
+            .assert()
+    }
+
+    fn run_verbose(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("syspass-cli")
+            .expect("Command should not have failed")
+            .env_clear()
+            .env("HOME", self.home.path())
+            .args(["-v", "-c", &self.config])
+            .args(args)
+            .assert()
+    }
+
+    fn json(&self, args: &[&str]) -> Value {
+        let output = self
+            .run(&[&["--output", "json"], args].concat())
+            .success()
+            .get_output()
+            .clone();
+
+        serde_json::from_slice(&output.stdout).expect("Output should be JSON")
+    }
+
+    fn stdout(&self, args: &[&str]) -> String {
+        let output = self.run(args).success().get_output().clone();
+
+        String::from_utf8(output.stdout).expect("Output should be UTF-8")
+    }
+
+    fn configure(&self, key: &str, value: Value) {
+        let mut config: Value =
+            serde_json::from_str(&std::fs::read_to_string(&self.config).expect("Failed to read config"))
+                .expect("Config is JSON");
+        config[key] = value;
+        std::fs::write(&self.config, config.to_string()).expect("Failed to write config");
+    }
+}
+
+fn get_id(record: &Value) -> String {
+    record["id"].as_u64().expect("Record should have an id").to_string()
+}
+
+#[test]
+fn run_account_flow_v3() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    let category = get_id(&cli.json(&["new", "category", "-n", "Databases", "-e", "Database servers"]));
+    let client = get_id(&cli.json(&["new", "client", "-n", "Acme", "-e", "Customer"]));
+    let account = get_id(&cli.json(&[
+        "new",
+        "password",
+        "-n",
+        "Production DB",
+        "-l",
+        "admin",
+        "-u",
+        "db.example.org",
+        "-o",
+        "Primary",
+        "-a",
+        &category,
+        "-i",
+        &client,
+        "-p",
+        "first-secret",
+    ]));
+
+    assert_eq!("first-secret\n", cli.stdout(&["get", "Production DB"]));
+    assert_eq!("admin\n", cli.stdout(&["get", &account, "--field", "login"]));
+
+    let found = cli.json(&["search", "production", "-u"]);
+    assert_eq!("Production DB", found["name"]);
+    assert_eq!("first-secret", found["password"]);
+
+    cli.run(&["edit", "account", "-i", &account, "-n", "Production database"])
+        .success();
+    assert_eq!(
+        "Production database\n",
+        cli.stdout(&["get", &account, "--field", "name"])
+    );
+
+    cli.run(&[
+        "edit",
+        "password",
+        "-i",
+        &account,
+        "-p",
+        "second-secret",
+        "-e",
+        "2030-01-01",
+    ])
+    .success();
+    assert_eq!("second-secret\n", cli.stdout(&["get", &account]));
+    for expiration in ["2024-13-45", "tomorrow"] {
+        cli.run(&[
+            "edit",
+            "password",
+            "-i",
+            &account,
+            "-p",
+            "third-secret",
+            "-e",
+            expiration,
+        ])
+        .failure()
+        .code(2);
+    }
+
+    let accounts = cli.json(&["list", "accounts", "-a", &category]);
+    assert_eq!(1, accounts.as_array().expect("List should be an array").len());
+
+    cli.run(&["remove", "account", "-i", &account]).success();
+    cli.run(&["get", &account]).failure().code(8);
+    cli.run(&["remove", "client", "-i", &client]).success();
+    cli.run(&["remove", "category", "-i", &category]).success();
+
+    assert!(server.methods().contains(&"account/editPass".to_owned()));
+}
+
+#[test]
+fn run_search_by_tag() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    cli.json(&[
+        "new",
+        "password",
+        "-n",
+        "Untagged",
+        "-l",
+        "admin",
+        "-a",
+        "1",
+        "-i",
+        "1",
+        "-p",
+        "untagged-secret",
+    ]);
+    let unused = get_id(&cli.json(&["new", "tag", "-n", "unused"]));
+
+    let found = cli.json(&["search", "--tag", "1", "-u"]);
+    assert_eq!("Seeded account", found["name"]);
+    assert_eq!("seed-password", found["password"]);
+    cli.run(&["search", "--tag", &unused]).failure().code(8);
+}
+
+#[test]
+fn run_edit_account_keeps_other_fields() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    cli.run(&["edit", "password", "-i", "1", "-p", "new-password", "-e", "2030-01-01"])
+        .success();
+    let before = cli.json(&["search", "Seeded account"]);
+
+    cli.run(&["edit", "account", "-i", "1", "-n", "Renamed account"])
+        .success();
+
+    let after = cli.json(&["search", "Renamed account"]);
+    for field in ["login", "url", "categoryId", "clientId", "passDateChange", "tagsId"] {
+        assert_eq!(before[field], after[field], "{field} changed");
+    }
+    assert_eq!("Renamed account", cli.json(&["search", "--tag", "1"])["name"]);
+}
+
+#[test]
+fn run_list_pages() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    for name in ["Zeta", "Alpha", "Mid"] {
+        cli.json(&[
+            "new",
+            "password",
+            "-n",
+            name,
+            "-l",
+            "admin",
+            "-a",
+            "1",
+            "-i",
+            "1",
+            "-p",
+            "secret-password",
+        ]);
+    }
+    let names = |args: &[&str]| -> Vec<String> {
+        cli.json(&[&["list", "accounts", "--sort", "name"], args].concat())
+            .as_array()
+            .expect("List should be an array")
+            .iter()
+            .map(|account| account["name"].as_str().unwrap_or_default().to_owned())
+            .collect()
+    };
+
+    assert_eq!(vec!["Alpha", "Mid"], names(&["--count", "2"]));
+    assert_eq!(vec!["Seeded account", "Zeta"], names(&["--count", "2", "--page", "2"]));
+    assert_eq!(vec!["Zeta"], names(&["--offset", "3"]));
+    cli.run(&["list", "accounts", "--page", "2"]).failure().code(2);
+}
+
+#[test]
+fn run_without_terminal_for_password_prompt() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, "");
+
+    let output = cli.run(&["get", "1"]).failure().code(130).get_output().clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}
+
+#[test_case(&["--set", "verifyHost"]; "malformed set")]
+#[test_case(&["--set", "verifyHost=maybe"]; "invalid value")]
+#[test_case(&["--profile", "missing"]; "missing profile")]
+#[test_case(&["--set", "apiVersion=SyspassV9"]; "unknown api version")]
+fn run_with_invalid_config(args: &[&str]) {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    let output = cli
+        .run(&[args, &["list", "accounts"]].concat())
+        .failure()
+        .code(12)
+        .get_output()
+        .clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}
+
+#[test]
+fn run_account_flow_v2() {
+    let server = FakeSyspass::start(Version::V2);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    let category = get_id(&cli.json(&["new", "category", "-n", "Databases", "-e", "Database servers"]));
+    let client = get_id(&cli.json(&["new", "client", "-n", "Acme", "-e", "Customer"]));
+    let account = get_id(&cli.json(&[
+        "new",
+        "password",
+        "-n",
+        "Production DB",
+        "-l",
+        "admin",
+        "-a",
+        &category,
+        "-i",
+        &client,
+        "-p",
+        "first-secret",
+    ]));
+
+    assert_eq!("first-secret\n", cli.stdout(&["get", "Production DB"]));
+    assert_eq!("admin\n", cli.stdout(&["get", &account, "--field", "login"]));
+
+    cli.run(&["edit", "account", "-i", &account, "-n", "Production database"])
+        .failure()
+        .code(11);
+    cli.run(&["search", "--tag", "1"]).failure().code(11);
+    cli.run(&["list", "accounts", "--tag", "1"]).failure().code(11);
+
+    cli.run(&["remove", "account", "-i", &account]).success();
+    cli.run(&["get", &account]).failure().code(8);
+    cli.run(&["remove", "client", "-i", &client]).success();
+    cli.run(&["remove", "category", "-i", &category]).success();
+
+    assert!(server.methods().contains(&"getAccountPassword".to_owned()));
+}
+
+#[test_case(Version::V2; "syspass-v2")]
+#[test_case(Version::V3; "syspass-v3")]
+fn run_with_wrong_token(version: Version) {
+    let server = FakeSyspass::start(version);
+    let cli = Cli::new(&server, "wrong-token", PASSWORD);
+
+    cli.run(&["list", "categories"]).failure().code(6);
+}
+
+#[test_case(Version::V2; "syspass-v2")]
+#[test_case(Version::V3; "syspass-v3")]
+fn run_with_wrong_password(version: Version) {
+    let server = FakeSyspass::start(version);
+    let cli = Cli::new(&server, TOKEN, "wrong-password");
+
+    cli.run(&["get", "1"]).failure().code(6);
+}
+
+#[test_case(Version::V2; "syspass-v2")]
+#[test_case(Version::V3; "syspass-v3")]
+fn run_without_permission(version: Version) {
+    let server = FakeSyspass::start(version);
+    let cli = Cli::new(&server, READ_ONLY_TOKEN, PASSWORD);
+
+    assert_eq!("seed-password\n", cli.stdout(&["get", "Seeded account"]));
+    cli.run(&["remove", "account", "-i", "1"]).failure().code(7);
+}
+
+#[test_case(Failure::Status(500), 5; "server error")]
+#[test_case(Failure::Body("<html>Maintenance</html>".to_owned()), 10; "malformed response")]
+#[test_case(Failure::Error("Internal error".to_owned()), 1; "server message")]
+fn run_with_injected_failure(failure: Failure, code: i32) {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    server.fail_next(failure);
+    cli.run(&["list", "accounts"]).failure().code(code);
+    cli.run(&["list", "accounts"]).success();
+}
+
+#[test]
+fn run_without_server() {
+    let home = tempdir().expect("Failed to create temp dir");
+    let config = write_config(home.path(), "http://127.0.0.1:1/api.php", Version::V3, TOKEN, PASSWORD);
+
+    Command::cargo_bin("syspass-cli")
+        .expect("Command should not have failed")
+        .env_clear()
+        .env("HOME", home.path())
+        .args(["-q", "-c", &config.to_string_lossy(), "list", "accounts"])
+        .assert()
+        .failure()
+        .code(3);
+}
+
+#[test]
+fn run_search_from_cache() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    cli.run(&["cache", "refresh"]).success();
+    let searches = |server: &FakeSyspass| {
+        server
+            .methods()
+            .iter()
+            .filter(|method| *method == "account/search")
+            .count()
+    };
+    let before = searches(&server);
+
+    let found = cli.json(&["search", "seeded", "-u", "-p"]);
+    assert_eq!("seed-password", found["password"]);
+    assert_eq!(before, searches(&server));
+
+    cli.json(&["search", "seeded", "-u", "-p", "--no-cache"]);
+    assert_eq!(before + 1, searches(&server));
+
+    cli.run(&["cache", "clear"]).success();
+    cli.run(&["cache", "status"]).failure().code(1);
+}
+
+#[test]
+fn run_replay_skips_cache() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    let recording = tempdir().expect("Failed to create temp dir");
+    let recording = recording.path().to_string_lossy().to_string();
+
+    cli.run(&["--record", &recording, "search", "seeded", "-u", "-p", "--no-cache"])
+        .success();
+    cli.json(&[
+        "new",
+        "password",
+        "-n",
+        "Seeded copy",
+        "-l",
+        "admin",
+        "-a",
+        "1",
+        "-i",
+        "1",
+        "-p",
+        "copy-secret",
+    ]);
+    cli.run(&["cache", "refresh"]).success();
+
+    // The cache would find both accounts, the recording only has one
+    cli.run(&["--replay", &recording, "search", "seeded", "-u", "-p"])
+        .success();
+}
+
+#[test]
+fn run_search_after_new_skips_cache() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    cli.run(&["cache", "refresh"]).success();
+    cli.json(&[
+        "new",
+        "password",
+        "-n",
+        "Seeded copy",
+        "-l",
+        "admin",
+        "-a",
+        "1",
+        "-i",
+        "1",
+        "-p",
+        "copy-secret",
+    ]);
+    let before = server
+        .methods()
+        .iter()
+        .filter(|method| *method == "account/search")
+        .count();
+
+    let output = cli
+        .run(&["--output", "json", "search", "seeded", "-u"])
+        .code(1)
+        .get_output()
+        .clone();
+    let found: Value = serde_json::from_slice(&output.stdout).expect("Output should be JSON");
+    let names: Vec<_> = found
+        .as_array()
+        .expect("Several accounts should be listed")
+        .iter()
+        .map(|account| account["name"].clone())
+        .collect();
+
+    assert!(names.contains(&Value::from("Seeded copy")), "{names:?}");
+    assert_eq!(
+        before + 1,
+        server
+            .methods()
+            .iter()
+            .filter(|method| *method == "account/search")
+            .count()
+    );
+}
+
+#[test_case("tests/import/accounts.csv", 3, 2; "csv")]
+#[test_case("tests/import/keepass.xml", 3, 3; "keepass")]
+#[test_case("tests/import/bitwarden.json", 2, 2; "bitwarden")]
+fn run_import(file: &str, imported: u64, categories: u64) {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    let summary = cli.json(&["import", file, "--dry-run"]);
+    assert_eq!(imported, summary["imported"]);
+    assert!(!server.methods().contains(&"account/create".to_owned()));
+
+    let summary = cli.json(&["import", file]);
+    assert_eq!(imported, summary["imported"]);
+    assert_eq!(categories, summary["categories"]);
+    assert_eq!("mail-secret\n", cli.stdout(&["get", "Mail"]));
+
+    let summary = cli.json(&["import", file]);
+    assert_eq!(0, summary["imported"]);
+    assert_eq!(imported, summary["duplicates"]);
+    assert_eq!(0, summary["categories"]);
+}
+
+#[test]
+fn run_import_retries_failed_entry() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    let file = cli.home.path().join("twice.csv");
+    std::fs::write(
+        &file,
+        "Title,Username,Password,URL\nRouter,admin,router-secret,\nRouter,admin,router-secret,\n",
+    )
+    .expect("Failed to write import file");
+
+    // The first copy fails, so the second one is no duplicate
+    server.fail_method("account/create", Failure::Error("Internal error".to_owned()));
+    let output = cli
+        .run(&["--output", "json", "import", &file.to_string_lossy()])
+        .failure()
+        .code(1)
+        .get_output()
+        .clone();
+    let summary: Value = serde_json::from_slice(&output.stdout).expect("Output should be JSON");
+    assert_eq!(1, summary["imported"]);
+    assert_eq!(1, summary["failed"]);
+    assert_eq!(0, summary["duplicates"]);
+    assert_eq!("router-secret\n", cli.stdout(&["get", "Router"]));
+}
+
+#[test_case("escrow.csv"; "csv")]
+#[test_case("escrow.xml"; "keepass")]
+fn run_export_and_import(file: &str) {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    let file = cli.home.path().join(file).to_string_lossy().to_string();
+
+    cli.run(&["export", &file]).failure().code(1);
+    cli.run(&["export", &file, "--insecure-plaintext"]).success();
+
+    let target = FakeSyspass::start(Version::V3);
+    let target_cli = Cli::new(&target, TOKEN, PASSWORD);
+    target_cli.run(&["remove", "account", "-i", "1"]).success();
+
+    let summary = target_cli.json(&["import", &file]);
+    assert_eq!(1, summary["imported"]);
+    assert_eq!("seed-password\n", target_cli.stdout(&["get", "Seeded account"]));
+}
+
+#[test]
+fn run_export_encrypted() {
+    use std::io::Read;
+
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public().to_string();
+
+    let output = cli
+        .run(&["export", "-", "--recipient", &recipient])
+        .success()
+        .get_output()
+        .clone();
+
+    let mut decrypted = String::new();
+    age::Decryptor::new(age::armor::ArmoredReader::new(output.stdout.as_slice()))
+        .expect("Export should be an age file")
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .expect("Failed to decrypt")
+        .read_to_string(&mut decrypted)
+        .expect("Failed to read");
+
+    let records: Value = serde_json::from_str(&decrypted).expect("Export should be JSON");
+    assert_eq!("seed-password", records[0]["password"]);
+}
+
+#[test]
+fn run_export_to_stdout_with_logs() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    let output = cli
+        .run_verbose(&["export", "-", "--insecure-plaintext"])
+        .success()
+        .get_output()
+        .clone();
+
+    let records: Value = serde_json::from_slice(&output.stdout).expect("Export should be JSON");
+    assert_eq!("seed-password", records[0]["password"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Writing passwords without encryption"), "{stderr}");
+    assert!(stderr.contains("Exported 1 account(s)"), "{stderr}");
+}
+
+#[test]
+fn run_admin() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+
+    let files = cli.json(&["admin", "backup"]);
+    assert_eq!("/var/www/html/sysPass/app/backup/sysPass_db-fake.sql.gz", files["db"]);
+    let files = cli.json(&["admin", "export", "--password", "export-secret", "--path", "/backup"]);
+    assert_eq!("/backup/sysPass_export-fake.xml", files["xml"]);
+    let files = cli.json(&["admin", "export"]);
+    assert_eq!("/var/www/html/sysPass/app/backup/sysPass_export-fake.xml", files["xml"]);
+    cli.run(&["admin", "export", "--password"]).failure().code(1);
+
+    let cli = Cli::new(&server, READ_ONLY_TOKEN, PASSWORD);
+    cli.run(&["admin", "backup"]).failure().code(7);
+
+    let server = FakeSyspass::start(Version::V2);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    cli.run(&["admin", "backup"]).failure().code(11);
+    cli.run(&["admin", "export"]).failure().code(11);
+}
+
+#[test]
+fn run_expiring() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    let date = (chrono::Utc::now() + chrono::Days::new(10))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    assert_eq!(Value::from(vec![] as Vec<Value>), cli.json(&["expiring"]));
+
+    cli.run(&["edit", "password", "-i", "1", "-p", "rotated", "-e", &date])
+        .success();
+    let expiring = cli.json(&["expiring", "--within", "30d"]);
+    assert_eq!(1, expiring[0]["id"]);
+    assert_eq!(date, expiring[0]["expires"]);
+    assert_eq!(10, expiring[0]["daysLeft"]);
+    assert_eq!(
+        Value::from(vec![] as Vec<Value>),
+        cli.json(&["expiring", "--within", "1w"])
+    );
+
+    cli.run(&["edit", "password", "-i", "1", "-p", "rotated", "-e", "2020-01-01"])
+        .success();
+    assert!(cli.json(&["expiring", "--within", "0"])[0]["daysLeft"].as_i64() < Some(0));
+
+    let server = FakeSyspass::start(Version::V2);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    cli.run(&["expiring"]).failure().code(11);
+}
+
+#[test]
+fn run_rotate() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    let log = cli.home.path().join("hook.log");
+    let hook = format!(
+        "cat >> '{0}' && echo \"rollback=$SYSPASS_ROLLBACK\" >> '{0}'",
+        log.display()
+    );
+    let read_log = || std::fs::read_to_string(&log).unwrap_or_default();
+
+    cli.run(&["rotate", "1"]).failure().code(1);
+    let rotations = cli.json(&["rotate", "--older-than", "90d", "--dry-run", "--no-hook"]);
+    assert_eq!("wouldRotate", rotations[0]["status"]);
+    let rotations = cli.json(&["rotate", "1", "--dry-run"]);
+    assert_eq!("wouldRotate", rotations[0]["status"]);
+    assert_eq!("", read_log());
+
+    let rotations = cli.json(&["rotate", "1", "--hook", &hook]);
+    assert_eq!("rotated", rotations[0]["status"]);
+    let password = cli.stdout(&["get", "1"]);
+    assert_ne!("seed-password\n", password);
+    assert_eq!(format!("{password}seed-password\nrollback=0\n"), read_log());
+    assert_eq!(
+        Value::from(vec![] as Vec<Value>),
+        cli.json(&["rotate", "--older-than", "90d", "--dry-run", "--no-hook"])
+    );
+
+    let read_only = Cli::new(&server, READ_ONLY_TOKEN, PASSWORD);
+    let output = read_only
+        .run(&["--output", "json", "rotate", "1", "--hook", &hook])
+        .failure()
+        .code(1)
+        .get_output()
+        .clone();
+    let rotations: Value = serde_json::from_slice(&output.stdout).expect("Output should be JSON");
+    assert_eq!("rolledBack", rotations[0]["status"]);
+    let log_lines = read_log();
+    let log_lines: Vec<_> = log_lines.lines().rev().take(3).collect();
+    assert_eq!(vec!["rollback=1", password.trim_end()], [log_lines[0], log_lines[2]]);
+    assert_eq!(password, cli.stdout(&["get", "1"]));
+
+    let applied = cli.home.path().join("applied");
+    let failing_rollback = format!(
+        "read new; echo \"$new\" > '{}'; test \"$SYSPASS_ROLLBACK\" = 0",
+        applied.display()
+    );
+    let output = read_only
+        .run_verbose(&["rotate", "1", "--hook", &failing_rollback])
+        .failure()
+        .code(1)
+        .get_output()
+        .clone();
+    let applied = std::fs::read_to_string(&applied).expect("Hook should have run");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("syspass-cli get 1"), "{stderr}");
+    assert!(!stderr.contains(applied.trim_end()), "{stderr}");
+
+    let before = read_log();
+    cli.configure("passwordRequirements", serde_json::json!({"minLength": 200}));
+    let output = cli
+        .run(&["--output", "json", "rotate", "1", "--hook", &hook])
+        .failure()
+        .code(1)
+        .get_output()
+        .clone();
+    let rotations: Value = serde_json::from_slice(&output.stdout).expect("Output should be JSON");
+    assert_eq!("failed", rotations[0]["status"]);
+    assert_eq!(before, read_log());
+    assert_eq!(password, cli.stdout(&["get", "1"]));
+    let rotations = cli.json(&["rotate", "1", "--no-hook", "--allow-weak"]);
+    assert_eq!("rotated", rotations[0]["status"]);
+
+    let server = FakeSyspass::start(Version::V2);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    cli.run(&["rotate", "1", "--no-hook"]).failure().code(11);
+}
+
+#[test]
+fn run_generate() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    cli.configure(
+        "passwordPolicies",
+        serde_json::json!({"pin": {"length": 6, "lowercase": false, "uppercase": false, "symbols": false}}),
+    );
+
+    let generated = cli.json(&["generate", "-n", "3", "-l", "16", "--no-symbols"]);
+    assert_eq!(3, generated.as_array().map_or(0, Vec::len));
+    let password = generated[0]["password"].as_str().unwrap_or_default();
+    assert_eq!(16, password.len());
+    assert!(password.chars().all(char::is_alphanumeric));
+    assert!(generated[0]["score"].as_f64().is_some());
+
+    let pin = cli.stdout(&["generate", "--policy", "pin"]);
+    assert!(pin.trim_end().len() == 6 && pin.trim_end().chars().all(|char| char.is_ascii_digit()));
+    cli.run(&["generate", "--policy", "missing"]).failure().code(1);
+
+    let passphrase = cli.json(&[
+        "generate",
+        "--passphrase",
+        "--words",
+        "4",
+        "--separator",
+        " ",
+        "--no-digit",
+    ]);
+    let words = passphrase[0]["password"].as_str().unwrap_or_default();
+    assert_eq!(4, words.split(' ').count());
+    assert!(passphrase[0]["entropy"].as_f64().is_some_and(|entropy| entropy > 51.0));
+    assert!(passphrase[0].get("score").is_none());
+    cli.run(&["generate", "--passphrase", "--length", "12"])
+        .failure()
+        .code(2);
+    cli.run(&["generate", "--passphrase", "--words", "0"]).failure().code(2);
+
+    let home = tempdir().expect("Failed to create temp dir");
+    Command::cargo_bin("syspass-cli")
+        .expect("Command should not have failed")
+        .env_clear()
+        .env("HOME", home.path())
+        .args(["generate", "-l", "12"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn run_password_requirements() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    let category = get_id(&cli.json(&["new", "category", "-n", "Lab", "-e", "Test devices"]));
+    cli.configure(
+        "passwordRequirements",
+        serde_json::json!({"minLength": 12, "symbols": true, "categories": {category.clone(): {"minLength": 4}}}),
+    );
+    let new = |password: &str, category: &str, extra: &[&str]| {
+        cli.run(
+            &[
+                &[
+                    "new", "password", "-n", "Router", "-l", "admin", "-a", category, "-i", "1", "-p", password,
+                ],
+                extra,
+            ]
+            .concat(),
+        )
+    };
+
+    new("short", "1", &[]).failure().code(1);
+    new("long-enough-pass", "1", &[]).success();
+    new("Dg7kL2pQ9zWm4x", "1", &[]).failure().code(1);
+    new("short", "1", &["--allow-weak"]).success();
+    new("short", &category, &[]).success();
+    new("abc", &category, &[]).failure().code(1);
+
+    let edit = |password: &str, extra: &[&str]| {
+        cli.run(
+            &[
+                &["edit", "password", "-i", "1", "-p", password, "-e", "2030-01-01"],
+                extra,
+            ]
+            .concat(),
+        )
+    };
+    edit("short", &[]).failure().code(1);
+    assert_eq!("seed-password\n", cli.stdout(&["get", "1"]));
+    edit("short", &["--allow-weak"]).success();
+    assert_eq!("short\n", cli.stdout(&["get", "1"]));
+}
+
+#[test]
+fn run_check_breach() {
+    let server = FakeSyspass::start(Version::V3);
+    let cli = Cli::new(&server, TOKEN, PASSWORD);
+    // SHA-1 of "seed-password" and "password123", sorted by hash
+    let breach_file = cli.home.path().join("pwned-passwords-sha1-ordered-by-hash.txt");
+    std::fs::write(
+        &breach_file,
+        "CA13909F7761A418CCADE60A2F5070D8049A2927:7\r\nCBFDAC6008F9CAB4083784CBD1874F76618D2A97:251682\r\n",
+    )
+    .expect("Failed to write breach file");
+
+    cli.run(&["check-breach", "--accounts"]).failure().code(1);
+    cli.run(&["check-breach", "--accounts", "--file", "/nonexistent/breach.txt"])
+        .failure()
+        .code(1);
+    cli.configure("breachFile", Value::String(breach_file.to_string_lossy().to_string()));
+
+    let output = cli
+        .run(&["--output", "json", "check-breach", "--accounts"])
+        .failure()
+        .code(1)
+        .get_output()
+        .clone();
+    let breached: Value = serde_json::from_slice(&output.stdout).expect("Output should be JSON");
+    assert_eq!(1, breached.as_array().expect("Output should be a list").len());
+    assert_eq!(7, breached[0]["count"]);
+
+    let new = |password: &str, extra: &[&str]| {
+        cli.run(
+            &[
+                &[
+                    "new", "password", "-n", "Router", "-l", "admin", "-a", "1", "-i", "1", "-p", password,
+                ],
+                extra,
+            ]
+            .concat(),
+        )
+    };
+    new("password123", &[]).failure().code(1);
+    new("password123", &["--allow-weak"]).success();
+    new("Dg7#kL2!pQ9zWm4x", &[]).success();
+
+    cli.run(&["edit", "password", "-i", "1", "-p", "password123", "-e", "2030-01-01"])
+        .failure()
+        .code(1);
+
+    let output = cli
+        .run(&["--output", "json", "check-breach", "--accounts"])
+        .failure()
+        .code(1)
+        .get_output()
+        .clone();
+    let breached: Value = serde_json::from_slice(&output.stdout).expect("Output should be JSON");
+    assert_eq!(2, breached.as_array().expect("Output should be a list").len());
+
+    cli.configure("breachFile", Value::String("/nonexistent/breach.txt".to_owned()));
+    new("Dg7#kL2!pQ9zWm4x", &[]).failure().code(12);
+    new("Dg7#kL2!pQ9zWm4x", &["--allow-weak"]).failure().code(12);
+}
@@ -0,0 +1,547 @@
+// A stateful stand-in for the sysPass JSON-RPC API, good enough to run the CLI against without docker
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+pub const TOKEN: &str = "fake-token";
+pub const READ_ONLY_TOKEN: &str = "fake-read-only-token";
+pub const PASSWORD: &str = "fake-password";
+
+const WRITE_ACTIONS: [&str; 9] = [
+    "create",
+    "edit",
+    "editPass",
+    "delete",
+    "addAccount",
+    "addCategory",
+    "addCustomer",
+    "backup",
+    "export",
+];
+
+#[derive(Clone, Copy)]
+pub enum Version {
+    V2,
+    V3,
+}
+
+impl Version {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::V2 => "SyspassV2",
+            Self::V3 => "SyspassV3",
+        }
+    }
+}
+
+pub enum Failure {
+    Status(u16),
+    Error(String),
+    Body(String),
+}
+
+#[derive(Clone)]
+struct Account {
+    id: u32,
+    name: String,
+    login: String,
+    url: String,
+    notes: String,
+    category_id: u32,
+    client_id: u32,
+    tags: Vec<u32>,
+    pass: String,
+    pass_date: i64,
+    pass_date_change: i64,
+}
+
+#[derive(Clone)]
+struct Named {
+    id: u32,
+    name: String,
+    description: String,
+}
+
+struct State {
+    accounts: BTreeMap<u32, Account>,
+    categories: BTreeMap<u32, Named>,
+    clients: BTreeMap<u32, Named>,
+    tags: BTreeMap<u32, Named>,
+    next_id: u32,
+    // A failure without a method is used by the next request
+    failures: VecDeque<(Option<String>, Failure)>,
+    methods: Vec<String>,
+}
+
+type Params = Map<String, Value>;
+type Response = Result<Value, String>;
+
+fn param(params: &Params, key: &str) -> String {
+    params.get(key).and_then(Value::as_str).unwrap_or_default().to_owned()
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs().try_into().unwrap_or_default())
+}
+
+fn id_param(params: &Params, key: &str) -> u32 {
+    param(params, key).parse().unwrap_or_default()
+}
+
+// sysPass reads id lists as arrays and ignores anything else
+fn ids_param(params: &Params, key: &str) -> Option<Vec<u32>> {
+    params.get(key)?.as_array().map(|items| {
+        items
+            .iter()
+            .filter_map(Value::as_u64)
+            .filter_map(|id| u32::try_from(id).ok())
+            .collect()
+    })
+}
+
+fn named(id: u32, name: &str, description: &str) -> Named {
+    Named {
+        id,
+        name: name.to_owned(),
+        description: description.to_owned(),
+    }
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            accounts: BTreeMap::from([(
+                1,
+                Account {
+                    id: 1,
+                    name: "Seeded account".to_owned(),
+                    login: "seed".to_owned(),
+                    url: "https://seed.example.org".to_owned(),
+                    notes: String::new(),
+                    category_id: 1,
+                    client_id: 1,
+                    tags: vec![1],
+                    pass: "seed-password".to_owned(),
+                    pass_date: 1_690_000_000,
+                    pass_date_change: 0,
+                },
+            )]),
+            categories: BTreeMap::from([(1, named(1, "Default", "Seeded category"))]),
+            clients: BTreeMap::from([(1, named(1, "Internal", "Seeded client"))]),
+            tags: BTreeMap::from([(1, named(1, "seeded", ""))]),
+            next_id: 2,
+            failures: VecDeque::new(),
+            methods: vec![],
+        }
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    fn search_accounts(&self, params: &Params) -> Vec<Account> {
+        let text = param(params, "text").to_lowercase();
+        let category_id = id_param(params, "categoryId");
+        let tags = ids_param(params, "tagsId").unwrap_or_default();
+
+        self.accounts
+            .values()
+            .filter(|account| account.name.to_lowercase().contains(&text))
+            .filter(|account| category_id == 0 || account.category_id == category_id)
+            .filter(|account| tags.iter().all(|tag| account.tags.contains(tag)))
+            .take(param(params, "count").parse().unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    fn account(&self, params: &Params) -> Result<Account, String> {
+        self.accounts
+            .get(&id_param(params, "id"))
+            .cloned()
+            .ok_or_else(|| "The account doesn't exist".to_owned())
+    }
+
+    // Like sysPass, every parameter but the password and tags replaces the stored value and a missing one is saved empty.
+    // Tags are only replaced by a non-empty tagsId array
+    fn save_account(&mut self, id: u32, params: &Params, client_key: &str) -> Account {
+        let current = self.accounts.get(&id).cloned();
+
+        let account = Account {
+            id,
+            name: param(params, "name"),
+            login: param(params, "login"),
+            url: param(params, "url"),
+            notes: param(params, "notes"),
+            category_id: id_param(params, "categoryId"),
+            client_id: id_param(params, client_key),
+            tags: ids_param(params, "tagsId")
+                .filter(|tags| !tags.is_empty())
+                .or_else(|| current.as_ref().map(|account| account.tags.clone()))
+                .unwrap_or_default(),
+            pass: params
+                .get("pass")
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned)
+                .or_else(|| current.as_ref().map(|account| account.pass.clone()))
+                .unwrap_or_default(),
+            pass_date: current.as_ref().map_or(1_690_000_000, |account| account.pass_date),
+            pass_date_change: param(params, "expireDate").parse().unwrap_or_default(),
+        };
+        self.accounts.insert(id, account.clone());
+
+        account
+    }
+
+    fn v3(&mut self, method: &str, params: &Params) -> Response {
+        let (entity, action) = method.split_once('/').ok_or("Method not found")?;
+
+        match (entity, action) {
+            ("account", "search") => Ok(v3_list(self.search_accounts(params).iter().map(v3_account).collect())),
+            ("account", "view") => Ok(v3_item(&v3_account(&self.account(params)?))),
+            ("account", "viewPass") => {
+                let account = self.account(params)?;
+                Ok(json!({"itemId": account.id, "result": {"password": account.pass}, "resultCode": 0}))
+            }
+            ("account", "create") => {
+                let id = self.next_id();
+                Ok(v3_item(&v3_account(&self.save_account(id, params, "clientId"))))
+            }
+            ("account", "edit") => {
+                let account = self.account(params)?;
+                Ok(v3_item(&v3_account(&self.save_account(account.id, params, "clientId"))))
+            }
+            ("account", "editPass") => {
+                let mut account = self.account(params)?;
+                account.pass = param(params, "pass");
+                account.pass_date = now();
+                account.pass_date_change = param(params, "expireDate").parse().unwrap_or_default();
+                self.accounts.insert(account.id, account.clone());
+                Ok(v3_item(&v3_account(&account)))
+            }
+            ("account", "delete") => {
+                let account = self.account(params)?;
+                self.accounts.remove(&account.id);
+                Ok(v3_item(&v3_account(&account)))
+            }
+            ("config", "backup") => Ok(v3_files(&json!({
+                "app": format!("{}/sysPass_app-fake.tar.gz", backup_path(params)),
+                "db": format!("{}/sysPass_db-fake.sql.gz", backup_path(params)),
+            }))),
+            ("config", "export") => Ok(v3_files(&json!({
+                "xml": format!("{}/sysPass_export-fake.xml", backup_path(params)),
+            }))),
+            (_, "search") => Ok(v3_list(self.named(entity)?.values().map(v3_named).collect())),
+            (_, "view") => self
+                .named(entity)?
+                .get(&id_param(params, "id"))
+                .map(|item| v3_item(&v3_named(item)))
+                .ok_or_else(|| format!("The {entity} doesn't exist")),
+            (_, "create" | "edit") => {
+                let id = if action == "create" {
+                    self.next_id()
+                } else {
+                    id_param(params, "id")
+                };
+                let items = self.named(entity)?;
+                if action == "edit" && !items.contains_key(&id) {
+                    return Err(format!("The {entity} doesn't exist"));
+                }
+                let item = named(id, &param(params, "name"), &param(params, "description"));
+                items.insert(id, item.clone());
+                Ok(v3_item(&v3_named(&item)))
+            }
+            (_, "delete") => self
+                .named(entity)?
+                .remove(&id_param(params, "id"))
+                .map(|item| v3_item(&v3_named(&item)))
+                .ok_or_else(|| format!("The {entity} doesn't exist")),
+            _ => Err("Method not found".to_owned()),
+        }
+    }
+
+    fn named(&mut self, entity: &str) -> Result<&mut BTreeMap<u32, Named>, String> {
+        match entity {
+            "category" | "Category" => Ok(&mut self.categories),
+            "client" | "Customer" => Ok(&mut self.clients),
+            "tag" => Ok(&mut self.tags),
+            _ => Err("Method not found".to_owned()),
+        }
+    }
+
+    fn v2(&mut self, method: &str, params: &Params) -> Response {
+        match method {
+            "getAccountSearch" => Ok(Value::from(
+                self.search_accounts(params)
+                    .iter()
+                    .map(|account| self.v2_account(account))
+                    .collect::<Vec<_>>(),
+            )),
+            "getAccountData" => Ok(self.v2_account(&self.account(params)?)),
+            "getAccountPassword" => {
+                let account = self.account(params)?;
+                Ok(json!({"itemId": account.id.to_string(), "pass": account.pass}))
+            }
+            "addAccount" => {
+                let id = self.next_id();
+                self.save_account(id, params, "customerId");
+                Ok(json!({"itemId": id, "result": "Account added", "resultCode": 0}))
+            }
+            "deleteAccount" => {
+                let account = self.account(params)?;
+                self.accounts.remove(&account.id);
+                Ok(json!({"itemId": account.id.to_string(), "result": "Account removed", "resultCode": 0}))
+            }
+            "getCategories" | "getCustomers" => {
+                let (entity, prefix) = if method == "getCategories" {
+                    ("Category", "category")
+                } else {
+                    ("Customer", "customer")
+                };
+                let mut result: Map<String, Value> = self
+                    .named(entity)?
+                    .values()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        (
+                            index.to_string(),
+                            json!({
+                                format!("{prefix}_id"): item.id.to_string(),
+                                format!("{prefix}_name"): item.name,
+                                format!("{prefix}_description"): item.description,
+                            }),
+                        )
+                    })
+                    .collect();
+                result.insert("count".to_owned(), Value::from(result.len()));
+                Ok(Value::Object(result))
+            }
+            "addCategory" | "addCustomer" => {
+                let id = self.next_id();
+                let item = named(id, &param(params, "name"), &param(params, "description"));
+                self.named(&method[3..])?.insert(id, item);
+                Ok(json!({"itemId": id, "result": format!("{} added", &method[3..]), "resultCode": 0}))
+            }
+            "deleteCategory" | "deleteCustomer" => {
+                let id = id_param(params, "id");
+                self.named(&method[6..])?
+                    .remove(&id)
+                    .ok_or_else(|| format!("{} not found", &method[6..]))?;
+                Ok(json!({"itemId": id.to_string(), "result": format!("{} removed", &method[6..]), "resultCode": 0}))
+            }
+            _ => Err("Method not found".to_owned()),
+        }
+    }
+
+    fn v2_account(&self, account: &Account) -> Value {
+        json!({
+            "account_id": account.id.to_string(),
+            "account_name": account.name,
+            "account_login": account.login,
+            "account_url": account.url,
+            "account_notes": account.notes,
+            "account_categoryId": account.category_id.to_string(),
+            "account_customerId": account.client_id.to_string(),
+            "account_countView": "0",
+            "account_pass": "",
+            "customer_name": self.clients.get(&account.client_id).map(|client| client.name.clone()).unwrap_or_default(),
+        })
+    }
+}
+
+// Like sysPass, accounts are returned without their tags
+fn v3_account(account: &Account) -> Value {
+    json!({
+        "id": account.id,
+        "name": account.name,
+        "login": account.login,
+        "url": account.url,
+        "notes": account.notes,
+        "categoryId": account.category_id,
+        "clientId": account.client_id,
+        "passDate": account.pass_date,
+        "passDateChange": account.pass_date_change,
+    })
+}
+
+fn v3_named(item: &Named) -> Value {
+    json!({"id": item.id, "name": item.name, "description": item.description, "isGlobal": 0})
+}
+
+fn v3_item(item: &Value) -> Value {
+    json!({"itemId": item["id"], "result": item, "resultCode": 0, "count": null})
+}
+
+fn v3_files(files: &Value) -> Value {
+    json!({"itemId": null, "result": {"files": files}, "resultCode": 0, "count": null})
+}
+
+// Like sysPass, an empty path is used as given and only a missing one falls back to the default
+fn backup_path(params: &Params) -> String {
+    params
+        .get("path")
+        .and_then(Value::as_str)
+        .map_or_else(|| "/var/www/html/sysPass/app/backup".to_owned(), ToOwned::to_owned)
+}
+
+fn v3_list(items: Vec<Value>) -> Value {
+    json!({"itemId": 0, "count": items.len(), "result": items, "resultCode": 0})
+}
+
+fn is_write(method: &str) -> bool {
+    let action = method.rsplit('/').next().unwrap_or(method);
+    WRITE_ACTIONS.contains(&action) || method.starts_with("delete")
+}
+
+fn handle(version: Version, state: &Mutex<State>, body: &str) -> (u16, String) {
+    let Ok(request) = serde_json::from_str::<Value>(body) else {
+        return (400, String::new());
+    };
+    let id = request["id"].clone();
+    let method = request["method"].as_str().unwrap_or_default().to_owned();
+    let params = request["params"].as_object().cloned().unwrap_or_default();
+
+    let mut state = state.lock().expect("Fake state is poisoned");
+    state.methods.push(method.clone());
+
+    let failure = state
+        .failures
+        .iter()
+        .position(|(only, _)| only.as_ref().is_none_or(|only| *only == method))
+        .and_then(|index| state.failures.remove(index))
+        .map(|(_, failure)| failure);
+    let result = match failure {
+        Some(Failure::Status(status)) => return (status, String::new()),
+        Some(Failure::Body(body)) => return (200, body),
+        Some(Failure::Error(message)) => Err(message),
+        None => {
+            let token = param(&params, "authToken");
+            let password = params.get("tokenPass").and_then(Value::as_str);
+
+            if token != TOKEN && token != READ_ONLY_TOKEN {
+                Err("Wrong authentication token".to_owned())
+            } else if password.is_some_and(|password| password != PASSWORD) {
+                Err("Wrong token password".to_owned())
+            } else if token == READ_ONLY_TOKEN && is_write(&method) {
+                Err("You don't have permission to do this operation".to_owned())
+            } else {
+                match version {
+                    Version::V2 => state.v2(&method, &params),
+                    Version::V3 => state.v3(&method, &params),
+                }
+            }
+        }
+    };
+
+    let response = match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "result": result, "id": id}),
+        Err(message) => {
+            json!({"jsonrpc": "2.0", "error": {"code": -32500, "message": message, "data": null}, "id": id})
+        }
+    };
+
+    (200, response.to_string())
+}
+
+fn serve(mut stream: TcpStream, version: Version, state: &Mutex<State>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut content_length = 0;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or_default();
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (status, response) = handle(version, state, &String::from_utf8_lossy(&body));
+    write!(
+        stream,
+        "HTTP/1.1 {status} Fake\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+        response.len()
+    )?;
+    stream.flush()
+}
+
+pub struct FakeSyspass {
+    version: Version,
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeSyspass {
+    pub fn start(version: Version) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind fake server");
+        let url = format!(
+            "http://{}/api.php",
+            listener.local_addr().expect("Fake server has an address")
+        );
+        let state = Arc::new(Mutex::new(State::new()));
+
+        let server_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = serve(stream, version, &server_state);
+            }
+        });
+
+        Self { version, url, state }
+    }
+
+    pub fn fail_next(&self, failure: Failure) {
+        self.state
+            .lock()
+            .expect("Fake state is poisoned")
+            .failures
+            .push_back((None, failure));
+    }
+
+    pub fn fail_method(&self, method: &str, failure: Failure) {
+        self.state
+            .lock()
+            .expect("Fake state is poisoned")
+            .failures
+            .push_back((Some(method.to_owned()), failure));
+    }
+
+    pub fn methods(&self) -> Vec<String> {
+        self.state.lock().expect("Fake state is poisoned").methods.clone()
+    }
+
+    pub fn write_config(&self, dir: &Path, token: &str, password: &str) -> PathBuf {
+        write_config(dir, &self.url, self.version, token, password)
+    }
+}
+
+pub fn write_config(dir: &Path, host: &str, version: Version, token: &str, password: &str) -> PathBuf {
+    let path = dir.join("config.json");
+    let config = json!({
+        "host": host,
+        "token": token,
+        "password": password,
+        "apiVersion": version.name(),
+        "verifyHost": false,
+        "noClipboard": true,
+        "noShell": true,
+    });
+    fs::write(&path, config.to_string()).expect("Failed to write config");
+
+    path
+}
@@ -20,6 +20,14 @@ fn run_help_without_arguments() {
     assert.failure().code(2);
 }
 
+#[test]
+fn run_invalid_output_format() {
+    let mut cmd = Command::cargo_bin("syspass-cli").expect("Command should not have failed");
+    let assert = cmd.args(["--output", "xml", "search", "test"]).assert();
+
+    assert.failure().code(2);
+}
+
 #[test_case("../test_config.json"; "syspass-v3")]
 #[test_case("../test_config_v2.json"; "syspass-v2")]
 #[ignore]
@@ -30,11 +38,11 @@ fn run_search_with_no_results(version: &str) {
         .assert();
 
     assert
-        .stdout(predicate::str::contains("\"jsonrpc\": String(\"2.0\"),"))
+        .stdout(predicate::str::contains("\"jsonrpc\": \"2.0\""))
         .stdout(predicate::str::contains("Sending request to "))
         .stdout(predicate::str::contains("Received response:"))
         .failure()
-        .code(1);
+        .code(8);
 }
 
 #[test_case("../test_config.json"; "syspass-v3")]
@@ -47,7 +55,7 @@ fn run_search_with_results(version: &str) {
         .assert();
 
     assert
-        .stdout(predicate::str::contains("\"jsonrpc\": String(\"2.0\"),"))
+        .stdout(predicate::str::contains("\"jsonrpc\": \"2.0\""))
         .stdout(predicate::str::contains("Sending request to "))
         .stdout(predicate::str::contains("Received response:"))
         .success()
@@ -77,7 +85,7 @@ fn run_change_with_data(version: &str, success: bool) {
     if success {
         assert.success().code(0);
     } else {
-        assert.failure().code(1);
+        assert.failure().code(11);
     }
 }
 
@@ -108,7 +116,7 @@ fn run_edit_category_client(version: &str, category_client: &str, success: bool)
     if success {
         assert.success().code(0);
     } else {
-        assert.failure().code(1);
+        assert.failure().code(11);
     }
 }
 